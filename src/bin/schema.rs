@@ -1,11 +1,13 @@
 use cosmwasm_schema::write_api;
 
-use truth_markets_contracts::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use truth_markets_contracts::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SudoMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         execute: ExecuteMsg,
         query: QueryMsg,
+        sudo: SudoMsg,
+        migrate: MigrateMsg,
     }
 }
@@ -0,0 +1,45 @@
+//! Query interface of the compliance contract that screens addresses before
+//! they trade.
+
+use std::fmt;
+
+use cosmwasm_schema::{cw_serde, QueryResponses};
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum ComplianceQueryMsg {
+    /// Whether `address` may take `action` in market `market_id`
+    #[returns(IsAllowedResponse)]
+    IsAllowed {
+        address: String,
+        action: ComplianceAction,
+        market_id: u64,
+    },
+}
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum ComplianceAction {
+    Buy,
+    Sell,
+    AddLiquidity,
+    /// Asked for both the sender and the recipient of the shares
+    TransferShares,
+}
+
+impl fmt::Display for ComplianceAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self {
+            ComplianceAction::Buy => "buy",
+            ComplianceAction::Sell => "sell",
+            ComplianceAction::AddLiquidity => "add_liquidity",
+            ComplianceAction::TransferShares => "transfer_shares",
+        };
+        write!(f, "{}", action)
+    }
+}
+
+#[cw_serde]
+pub struct IsAllowedResponse {
+    pub allowed: bool,
+}
@@ -1,23 +1,53 @@
+use std::str::FromStr;
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    to_json_binary, Addr, Api, Attribute, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Empty,
+    Env, Event, Isqrt, MessageInfo, Order, Response, StdError, StdResult, Storage, Timestamp,
     Uint128,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::Bound;
+use cw_utils::{must_pay, nonpayable, one_coin};
+
+use semver::Version;
 
+use crate::compliance::{ComplianceAction, ComplianceQueryMsg, IsAllowedResponse};
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, GetCountResponse, InstantiateMsg, QueryMsg};
+use crate::migrations::migrate_v0_1_markets;
+use crate::msg::{
+    CreateMarketMsg, CreatorReputationResponse, DisputeRoundInfo, DisputeRoundsResponse,
+    EventGroupResponse, ExecuteMsg, GroupMarketPrice, HasRoleResponse, HeadroomResponse,
+    InstantiateMsg, KeeperPoolResponse, MarketResponse, MarketsResponse, MigrateMsg,
+    OracleReportsResponse, OutcomeTally, ParticipantsResponse, PendingOwnerResponse,
+    ProposalResponse, QueryMsg, ResolutionHistoryResponse, ResolverMsg, RoleMembersResponse,
+    SudoMsg, UpdateConfigMsg, Vote, VotesResponse,
+};
+use crate::oracle::{AnswerResponse, OracleQueryMsg};
+use crate::price_feed::{Price, PriceFeedQueryMsg, PriceFeedResponse};
 use crate::state::{
-    Buyer, LiquidityProvider, Market, BUYERS, LIQUIDITY_PROVIDERS, MARKETS, MARKET_COUNT, STATE,
+    Buyer, Config, DisputeRound, EventGroup, LiquidityProvider, Market, MarketStatus, OracleReport,
+    OracleSource, PendingOwner, PositionLimit, Proposal, ResolutionAction, ResolutionStep,
+    Resolver, Role, Trade, TradeSide, Visibility, VoidFallback, BUYERS, CHILD_MARKETS, CLOSE_QUEUE,
+    CONFIG, CREATOR_STATS, DISPUTE_ROUNDS, EVENT_GROUPS, EVENT_GROUP_COUNT, FINALIZE_QUEUE,
+    KEEPER_POOL, LIQUIDITY_PROVIDERS, MARKETS, MARKET_COUNT, ORACLE_REPORTS, PARTICIPANTS,
+    PENDING_OWNER, PROPOSALS, RESOLUTION_LOG, ROLES, TRADES, VOTES,
 };
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:truth-markets-contracts";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 // Constants
-const FEE_PERCENTAGE: u128 = 2; // 2% trading fee
-const DECIMAL_PRECISION: u128 = 1_000_000_00; // For fractional calculations
+const DECIMAL_PRECISION: u128 = 100_000_000; // For fractional calculations
+const PRICE_SUM_TOLERANCE: u128 = 1_000_000; // 1% of DECIMAL_PRECISION
+
+// How long before the close an event time may lie, bounding the trades it reverts
+const MAX_EVENT_LEAD: u64 = 86_400;
+
+// Pagination
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -28,44 +58,269 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    let config = new_config(deps.api, info.sender.clone(), msg)?;
+    CONFIG.save(deps.storage, &config)?;
+
     Ok(Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("owner", info.sender))
-    // .add_attribute("count", msg.count.to_string()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::WrongContract {
+            contract: stored.contract,
+        });
+    }
+    let from = parse_version(&stored.version)?;
+    if from > parse_version(CONTRACT_VERSION)? {
+        return Err(ContractError::CannotDowngrade {
+            from: stored.version,
+            to: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    let mut response = Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION);
+
+    // v0.1 had no config and markets without a schedule
+    if from < Version::new(0, 2, 0) {
+        let config = match (CONFIG.may_load(deps.storage)?, msg.owner, msg.config) {
+            (Some(config), _, _) => config,
+            (None, Some(owner), Some(config)) => {
+                let owner = deps.api.addr_validate(&owner)?;
+                new_config(deps.api, owner, config)?
+            }
+            _ => return Err(ContractError::MissingConfig {}),
+        };
+        CONFIG.save(deps.storage, &config)?;
+
+        let close_time = msg.legacy_close_time.unwrap_or(env.block.time);
+        let migrated = migrate_v0_1_markets(deps.storage, &config.default_collateral, close_time)?;
+        response = response.add_attribute("migrated_markets", migrated.to_string());
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(response)
+}
+
+fn parse_version(version: &str) -> Result<Version, ContractError> {
+    version.parse().map_err(|_| ContractError::InvalidVersion {
+        version: version.to_string(),
+    })
+}
+
+/// Settings from an instantiate message, arbiter and treasury defaulting to `owner`
+fn new_config(api: &dyn Api, owner: Addr, msg: InstantiateMsg) -> Result<Config, ContractError> {
+    let arbiter = match msg.arbiter {
+        Some(arbiter) => api.addr_validate(&arbiter)?,
+        None => owner.clone(),
+    };
+    let config = Config {
+        owner: Some(owner.clone()),
+        arbiter,
+        proposal_bond: msg.proposal_bond,
+        liveness: msg.liveness,
+        price_feeds: msg
+            .price_feeds
+            .iter()
+            .map(|feed| api.addr_validate(feed))
+            .collect::<StdResult<_>>()?,
+        max_price_age: msg.max_price_age,
+        max_confidence_bps: msg.max_confidence_bps,
+        max_dispute_rounds: msg.max_dispute_rounds,
+        creator_bond: msg.creator_bond,
+        treasury: match msg.treasury {
+            Some(treasury) => api.addr_validate(&treasury)?,
+            None => owner,
+        },
+        slash_treasury_bps: msg.slash_treasury_bps,
+        keeper_reward: msg.keeper_reward,
+        default_collateral: msg.default_collateral,
+        trading_fee_bps: msg.trading_fee_bps,
+        min_liquidity: msg.min_liquidity,
+        max_liquidity: msg.max_liquidity.unwrap_or(Uint128::MAX),
+        collateral_min_liquidity: msg.collateral_min_liquidity,
+        creation_fee: msg.creation_fee,
+        spam_deposit: msg.spam_deposit,
+        min_activity: msg.min_activity,
+        open_market_creation: msg.open_market_creation,
+        paused: false,
+        compliance: msg
+            .compliance
+            .map(|compliance| api.addr_validate(&compliance))
+            .transpose()?,
+    };
+    validate_config(&config)?;
+    Ok(config)
+}
+
+fn validate_config(config: &Config) -> Result<(), ContractError> {
+    for bps in [
+        config.max_confidence_bps,
+        config.slash_treasury_bps,
+        config.trading_fee_bps,
+    ] {
+        if bps > 10_000 {
+            return Err(ContractError::InvalidBps { bps });
+        }
+    }
+    if std::iter::once(config.min_liquidity)
+        .chain(config.collateral_min_liquidity.iter().map(|min| min.amount))
+        .any(|min| min > config.max_liquidity)
+    {
+        return Err(ContractError::InvalidLiquidityRange {});
+    }
+    Ok(())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::CreateMarket {
-            description,
-            initial_liquidity,
-        } => execute::create_market(deps, info, description, initial_liquidity),
+        ExecuteMsg::CreateMarket(msg) => execute::create_market(deps, env, info, msg),
         ExecuteMsg::AddLiquidity { market_id, amount } => {
-            execute::add_liquidity(deps, info, market_id, amount)
+            execute::add_liquidity(deps, env, info, market_id, amount)
         }
         ExecuteMsg::RemoveLiquidity { market_id, amount } => {
-            execute::remove_liquidity(deps, info, market_id, amount)
+            execute::remove_liquidity(deps, env, info, market_id, amount)
         }
         ExecuteMsg::BuyShares {
             market_id,
             outcome,
             amount,
-        } => execute::buy_shares(deps, info, market_id, outcome, amount),
+        } => execute::buy_shares(deps, env, info, market_id, outcome, amount),
         ExecuteMsg::SellShares {
             market_id,
             outcome,
             amount,
-        } => execute::sell_shares(deps, info, market_id, outcome, amount),
+        } => execute::sell_shares(deps, env, info, market_id, outcome, amount),
         ExecuteMsg::ResolveMarket {
             market_id,
             winning_outcome,
-        } => execute::resolve_market(deps, info, market_id, winning_outcome),
+            evidence,
+            event_time,
+        } => execute::resolve_market(
+            deps,
+            env,
+            info,
+            market_id,
+            winning_outcome,
+            evidence,
+            event_time,
+        ),
+        ExecuteMsg::FinalizeMarket { market_id } => {
+            execute::finalize_market(deps, env, info, market_id)
+        }
+        ExecuteMsg::CancelMarket { market_id } => {
+            execute::cancel_market(deps, env, info, market_id)
+        }
+        ExecuteMsg::TransferShares {
+            market_id,
+            recipient,
+            outcome,
+            amount,
+        } => execute::transfer_shares(deps, env, info, market_id, recipient, outcome, amount),
+        ExecuteMsg::MergePositions { market_id, amount } => {
+            execute::merge_positions(deps, env, info, market_id, amount)
+        }
+        ExecuteMsg::CreateEventGroup {
+            description,
+            market_ids,
+        } => execute::create_event_group(deps, env, info, description, market_ids),
+        ExecuteMsg::ConvertNoPositions {
+            group_id,
+            market_ids,
+            amount,
+        } => execute::convert_no_positions(deps, env, info, group_id, market_ids, amount),
+        ExecuteMsg::ProposeOutcome {
+            market_id,
+            outcome,
+            evidence,
+            event_time,
+        } => execute::propose_outcome(deps, env, info, market_id, outcome, evidence, event_time),
+        ExecuteMsg::DisputeOutcome {
+            market_id,
+            outcome,
+            evidence,
+        } => execute::dispute_outcome(deps, env, info, market_id, outcome, evidence),
+        ExecuteMsg::ArbitrateDispute {
+            market_id,
+            outcome,
+            evidence,
+            event_time,
+        } => execute::arbitrate_dispute(deps, env, info, market_id, outcome, evidence, event_time),
+        ExecuteMsg::VoteOutcome {
+            market_id,
+            outcome,
+            evidence,
+        } => execute::vote_outcome(deps, env, info, market_id, outcome, evidence),
+        ExecuteMsg::ObservePrice { market_id } => execute::observe_price(deps, env, market_id),
+        ExecuteMsg::ResolveFromOracle { market_id } => {
+            execute::resolve_from_oracle(deps, env, info, market_id)
+        }
+        ExecuteMsg::Claim { market_id } => execute::claim(deps, env, info, market_id),
+        ExecuteMsg::FundKeeperPool {} => execute::fund_keeper_pool(deps, info),
+        ExecuteMsg::UpdateConfig(msg) => execute::update_config(deps, info, msg),
+        ExecuteMsg::ProposeNewOwner { owner, expiry } => {
+            execute::propose_new_owner(deps, env, info, owner, expiry)
+        }
+        ExecuteMsg::AcceptOwnership {} => execute::accept_ownership(deps, env, info),
+        ExecuteMsg::RenounceOwnership {} => execute::renounce_ownership(deps, info),
+        ExecuteMsg::Pause { market_id } => execute::set_paused(deps, info, market_id, true),
+        ExecuteMsg::Unpause { market_id } => execute::set_paused(deps, info, market_id, false),
+        ExecuteMsg::EmergencyShutdown {
+            market_id,
+            start_after,
+            limit,
+        } => execute::emergency_shutdown(deps, env, info, market_id, start_after, limit),
+        ExecuteMsg::SetMarketLimits {
+            market_id,
+            max_position,
+            max_open_interest,
+        } => execute::set_market_limits(deps, info, market_id, max_position, max_open_interest),
+        ExecuteMsg::AddParticipants {
+            market_id,
+            addresses,
+        } => execute::update_participants(deps, info, market_id, addresses, true),
+        ExecuteMsg::RemoveParticipants {
+            market_id,
+            addresses,
+        } => execute::update_participants(deps, info, market_id, addresses, false),
+        ExecuteMsg::GrantRole { role, address } => {
+            execute::update_role(deps, info, role, address, true)
+        }
+        ExecuteMsg::RevokeRole { role, address } => {
+            execute::update_role(deps, info, role, address, false)
+        }
+        ExecuteMsg::CloseExpired { limit } => {
+            execute::close_expired(deps, env, Some(info.sender), limit)
+        }
+        ExecuteMsg::FinalizeResolved { limit } => {
+            execute::finalize_resolved(deps, env, Some(info.sender), limit)
+        }
+        ExecuteMsg::ProcessClaims { market_id, limit } => {
+            execute::process_claims(deps, env, Some(info.sender), market_id, limit)
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::CloseExpired { limit } => execute::close_expired(deps, env, None, limit),
+        SudoMsg::FinalizeResolved { limit } => execute::finalize_resolved(deps, env, None, limit),
+        SudoMsg::ProcessClaims { market_id, limit } => {
+            execute::process_claims(deps, env, None, market_id, limit)
+        }
     }
 }
 
@@ -75,22 +330,108 @@ pub mod execute {
     /// Create a new prediction market
     pub fn create_market(
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
-        description: String,
-        initial_liquidity: Uint128,
+        msg: CreateMarketMsg,
     ) -> Result<Response, ContractError> {
+        let CreateMarketMsg {
+            description,
+            initial_liquidity,
+            close_time,
+            resolution_time,
+            void_fallback,
+            condition,
+            resolver,
+            max_position,
+            max_open_interest,
+            visibility,
+        } = msg;
         let market_id = MARKET_COUNT.may_load(deps.storage)?.unwrap_or(0) + 1;
+        let config = CONFIG.load(deps.storage)?;
 
-        if initial_liquidity.is_zero() {
+        if config.paused {
+            return Err(ContractError::Paused {});
+        }
+        if !config.open_market_creation
+            && !has_role(deps.storage, Role::MarketCreator, &info.sender)?
+        {
             return Err(ContractError::Unauthorized {});
         }
 
-        let price_yes = initial_liquidity / (initial_liquidity + initial_liquidity);
-        let price_no = initial_liquidity / (initial_liquidity + initial_liquidity);
+        if initial_liquidity.is_zero() {
+            return Err(ContractError::ZeroLiquidity {});
+        }
+
+        if close_time <= env.block.time {
+            return Err(ContractError::InvalidCloseTime {});
+        }
+
+        if resolution_time < close_time {
+            return Err(ContractError::InvalidResolutionTime {});
+        }
+
+        let resolver = validate_resolver(deps.as_ref(), resolver, close_time, resolution_time)?;
+
+        // Creators resolving their own market as resolvers back it with a bond
+        let creator_bond = match resolver {
+            Resolver::Creator => config.creator_bond,
+            _ => Uint128::zero(),
+        };
+
+        // The initial liquidity also fixes the collateral denom of the market
+        let collateral = one_coin(&info)?;
+        let min_liquidity = config
+            .collateral_min_liquidity
+            .iter()
+            .find(|min| min.denom == collateral.denom)
+            .map_or(config.min_liquidity, |min| min.amount);
+        if initial_liquidity < min_liquidity || initial_liquidity > config.max_liquidity {
+            return Err(ContractError::LiquidityOutOfRange {
+                min: min_liquidity,
+                max: config.max_liquidity,
+            });
+        }
+
+        let creation_fee = amount_in(&config.creation_fee, &collateral.denom);
+        let spam_deposit = amount_in(&config.spam_deposit, &collateral.denom);
+        let expected = initial_liquidity + creator_bond + creation_fee + spam_deposit;
+        if collateral.amount != expected {
+            return Err(ContractError::FundsMismatch {
+                expected,
+                received: collateral.amount,
+            });
+        }
+
+        // A conditional market needs a live parent in the same collateral
+        if let Some(condition) = &condition {
+            let parent = MARKETS.load(deps.storage, condition.market_id)?;
+            let status = parent.status_at(env.block.time);
+            if matches!(status, MarketStatus::Voided | MarketStatus::Finalized) {
+                return Err(ContractError::InvalidCondition {
+                    market_id: condition.market_id,
+                    status,
+                });
+            }
+            if !matches!(condition.outcome.as_str(), "YES" | "NO") {
+                return Err(ContractError::InvalidOutcome {
+                    outcome: condition.outcome.clone(),
+                });
+            }
+            if parent.collateral_denom != collateral.denom {
+                return Err(ContractError::CollateralMismatch {
+                    denom: collateral.denom,
+                    expected: parent.collateral_denom,
+                });
+            }
+            CHILD_MARKETS.save(deps.storage, (condition.market_id, market_id), &Empty {})?;
+        }
 
         let shares_yes = initial_liquidity;
         let shares_no = initial_liquidity;
 
+        let price_yes = calculate_price(shares_yes, shares_no);
+        let price_no = calculate_price(shares_no, shares_yes);
+
         let total_liquidity_shares = shares_yes * shares_no;
 
         //    let new_total_liquidity = new_shares_yes * new_shares_no;
@@ -102,23 +443,73 @@ pub mod execute {
             shares_yes: initial_liquidity,
             shares_no: initial_liquidity,
             total_liquidity: initial_liquidity,
-            resolved: false,
+            collateral_denom: collateral.denom,
+            status: MarketStatus::Open,
+            created_at: env.block.time,
+            close_time,
+            resolution_time,
             winning_outcome: None,
-            price_yes: price_yes,
-            price_no: price_no,
-            total_liquidity_shares: total_liquidity_shares,
+            price_yes,
+            price_no,
+            total_liquidity_shares,
+            void_fallback: void_fallback.unwrap_or_default(),
+            condition,
+            group_id: None,
+            resolver,
+            creator_bond,
+            spam_deposit,
+            volume: Uint128::zero(),
+            max_position,
+            max_open_interest,
+            open_interest: Uint128::zero(),
+            collateral: initial_liquidity,
+            event_time: None,
+            paused: false,
+            shutdown: false,
+            visibility: visibility.unwrap_or_default(),
         };
 
         MARKETS.save(deps.storage, market_id, &market)?;
         MARKET_COUNT.save(deps.storage, &market_id)?;
+        CLOSE_QUEUE.save(deps.storage, (close_time.seconds(), market_id), &Empty {})?;
+        if let Some(deadline) = committee_deadline(&market) {
+            FINALIZE_QUEUE.save(deps.storage, (deadline.seconds(), market_id), &Empty {})?;
+        }
 
-        Ok(Response::new()
+        let mut stats = CREATOR_STATS
+            .may_load(deps.storage, &info.sender)?
+            .unwrap_or_default();
+        stats.markets_created += 1;
+        CREATOR_STATS.save(deps.storage, &info.sender, &stats)?;
+
+        // The creator owns the initial liquidity like any other provider
+        LIQUIDITY_PROVIDERS.save(
+            deps.storage,
+            (market_id, info.sender.clone()),
+            &LiquidityProvider {
+                address: info.sender,
+                contributed_liquidity: initial_liquidity,
+            },
+        )?;
+
+        let mut response = Response::new();
+        if !creation_fee.is_zero() {
+            response = response.add_message(BankMsg::Send {
+                to_address: config.treasury.to_string(),
+                amount: vec![Coin::new(creation_fee, market.collateral_denom)],
+            });
+        }
+
+        Ok(response
             .add_attribute("action", "create_market")
-            .add_attribute("market_id", market_id.to_string()))
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("close_time", close_time.seconds().to_string())
+            .add_attribute("resolution_time", resolution_time.seconds().to_string()))
     }
     /// Add liquidity to an existing market
     pub fn add_liquidity(
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         market_id: u64,
         amount: Uint128,
@@ -126,11 +517,15 @@ pub mod execute {
         //When a Liquidity Provider adds liquidity to a market, they in fact increase the number of shares in all pools in that market.
         let mut market = MARKETS.load(deps.storage, market_id)?;
 
-        if market.resolved {
-            return Err(ContractError::Std(StdError::generic_err(
-                "Cannot add liquidity to a resolved market",
-            )));
-        }
+        ensure_open(&market, &env, market_id)?;
+        ensure_not_paused(deps.storage, &market, market_id)?;
+        ensure_allowed(
+            deps.as_ref(),
+            &info.sender,
+            ComplianceAction::AddLiquidity,
+            market_id,
+        )?;
+        ensure_participant(deps.storage, &market, market_id, &info.sender)?;
 
         if amount.is_zero() {
             return Err(ContractError::Std(StdError::generic_err(
@@ -138,6 +533,8 @@ pub mod execute {
             )));
         }
 
+        ensure_funds(&info, &market.collateral_denom, amount)?;
+
         // Calculate the invariant before adding liquidity
         // let invariant = market.shares_yes * market.shares_no;
 
@@ -204,6 +601,9 @@ pub mod execute {
                         address: info.sender.clone(),
                         shares_yes: Uint128::zero(),
                         shares_no: Uint128::zero(),
+                        reverted_cost: Uint128::zero(),
+                        reverted_proceeds: Uint128::zero(),
+                        spent: Uint128::zero(),
                     });
                     buyer.shares_yes += shares_yes_to_provider;
                     buyer.shares_no += shares_no_to_provider;
@@ -237,6 +637,7 @@ pub mod execute {
         // Update market prices
         market.price_yes = calculate_price(market.shares_yes, market.shares_no);
         market.price_no = calculate_price(market.shares_no, market.shares_yes);
+        market.collateral += amount;
 
         MARKETS.save(deps.storage, market_id, &market)?;
 
@@ -249,17 +650,15 @@ pub mod execute {
     /// Remove liquidity from an existing market
     pub fn remove_liquidity(
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         market_id: u64,
         amount: Uint128,
     ) -> Result<Response, ContractError> {
         let mut market = MARKETS.load(deps.storage, market_id)?;
 
-        if market.resolved {
-            return Err(ContractError::Std(StdError::generic_err(
-                "Cannot remove liquidity from a resolved market",
-            )));
-        }
+        // Not held by a pause: providers keep a way out of a halted market
+        ensure_open(&market, &env, market_id)?;
 
         if amount.is_zero() {
             return Err(ContractError::Std(StdError::generic_err(
@@ -278,12 +677,19 @@ pub mod execute {
             )));
         }
 
-        // Calculate the share of the liquidity pool the provider owns
-        let liquidity_share = amount * market.total_liquidity / provider.contributed_liquidity;
+        if amount == market.total_liquidity {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Cannot remove the last liquidity of an open market",
+            )));
+        }
 
-        // Calculate the shares to withdraw from each outcome pool
-        let shares_yes_to_withdraw = liquidity_share * market.shares_yes / market.total_liquidity;
-        let shares_no_to_withdraw = liquidity_share * market.shares_no / market.total_liquidity;
+        // The provider takes its pro-rata part of each outcome pool
+        let shares_yes_to_withdraw = market
+            .shares_yes
+            .multiply_ratio(amount, market.total_liquidity);
+        let shares_no_to_withdraw = market
+            .shares_no
+            .multiply_ratio(amount, market.total_liquidity);
 
         // Update the market state
         market.shares_yes -= shares_yes_to_withdraw;
@@ -292,7 +698,33 @@ pub mod execute {
 
         // Update the liquidity provider's contribution
         provider.contributed_liquidity -= amount;
-        LIQUIDITY_PROVIDERS.save(deps.storage, (market_id, info.sender.clone()), &provider)?;
+        if provider.contributed_liquidity.is_zero() {
+            LIQUIDITY_PROVIDERS.remove(deps.storage, (market_id, info.sender.clone()));
+        } else {
+            LIQUIDITY_PROVIDERS.save(deps.storage, (market_id, info.sender.clone()), &provider)?;
+        }
+
+        // Full YES+NO sets are redeemed for collateral, the shares left over
+        // join the provider's position
+        let collateral = shares_yes_to_withdraw.min(shares_no_to_withdraw);
+        let leftover_yes = shares_yes_to_withdraw - collateral;
+        let leftover_no = shares_no_to_withdraw - collateral;
+        market.collateral = market.collateral.saturating_sub(collateral);
+        if !(leftover_yes + leftover_no).is_zero() {
+            let mut buyer = BUYERS
+                .may_load(deps.storage, (market_id, info.sender.clone()))?
+                .unwrap_or(Buyer {
+                    address: info.sender.clone(),
+                    shares_yes: Uint128::zero(),
+                    shares_no: Uint128::zero(),
+                    reverted_cost: Uint128::zero(),
+                    reverted_proceeds: Uint128::zero(),
+                    spent: Uint128::zero(),
+                });
+            buyer.shares_yes += leftover_yes;
+            buyer.shares_no += leftover_no;
+            BUYERS.save(deps.storage, (market_id, info.sender.clone()), &buyer)?;
+        }
 
         // Update market prices
         market.price_yes = calculate_price(market.shares_yes, market.shares_no);
@@ -300,13 +732,19 @@ pub mod execute {
 
         MARKETS.save(deps.storage, market_id, &market)?;
 
-        // Send the withdrawn funds to the provider (pseudo-code, replace with actual token transfer logic)
-        // deps.querier.send_tokens(&info.sender, amount)?;
+        let mut response = Response::new();
+        if !collateral.is_zero() {
+            response = response.add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: vec![Coin::new(collateral, market.collateral_denom)],
+            });
+        }
 
-        Ok(Response::new()
+        Ok(response
             .add_attribute("action", "remove_liquidity")
             .add_attribute("market_id", market_id.to_string())
             .add_attribute("liquidity_removed", amount.to_string())
+            .add_attribute("collateral_returned", collateral.to_string())
             .add_attribute("shares_yes_withdrawn", shares_yes_to_withdraw.to_string())
             .add_attribute("shares_no_withdrawn", shares_no_to_withdraw.to_string())
             .add_attribute("price_yes", market.price_yes.to_string())
@@ -316,6 +754,7 @@ pub mod execute {
     /// Buy shares (at the current price)
     pub fn buy_shares(
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         market_id: u64,
         outcome: String,
@@ -323,21 +762,24 @@ pub mod execute {
     ) -> Result<Response, ContractError> {
         let mut market = MARKETS.load(deps.storage, market_id)?;
 
-        if market.resolved {
-            return Err(ContractError::Std(StdError::generic_err(
-                "Cannot trade in a resolved market",
-            )));
-        }
+        ensure_open(&market, &env, market_id)?;
+        ensure_not_paused(deps.storage, &market, market_id)?;
+        ensure_allowed(
+            deps.as_ref(),
+            &info.sender,
+            ComplianceAction::Buy,
+            market_id,
+        )?;
+        ensure_participant(deps.storage, &market, market_id, &info.sender)?;
 
-        // Apply trading fee
-        let fee = amount * Uint128::from(FEE_PERCENTAGE as u128) / Uint128::from(100u128);
-        let net_amount: Uint128 = amount;
+        ensure_funds(&info, &market.collateral_denom, amount)?;
 
-        //TODO let's see what we do with the fee?
+        // The trading fee goes to the treasury, the rest buys shares
+        let config = CONFIG.load(deps.storage)?;
+        let fee = amount.multiply_ratio(config.trading_fee_bps, 10_000u128);
+        let net_amount = amount - fee;
 
-        // Add fee to the market's total liquidity (to be distributed later)
-        //   let net_amount = amount - fee;
-        // market.total_liquidity += fee;
+        let pool_before = (market.shares_yes, market.shares_no);
 
         // Constant product invariant
         // let invariant = market.shares_yes * market.shares_no;
@@ -363,25 +805,6 @@ pub mod execute {
                 market.price_yes = new_price_yes;
                 market.price_no = new_price_no;
 
-                // Update buyer's shares
-                BUYERS.update(
-                    deps.storage,
-                    (market_id, info.sender.clone()),
-                    |record| -> StdResult<Buyer> {
-                        let mut buyer = record.unwrap_or(Buyer {
-                            address: info.sender.clone(),
-                            shares_yes: Uint128::zero(),
-                            shares_no: Uint128::zero(),
-                        });
-                        if outcome == "YES" {
-                            buyer.shares_yes += shares_bought;
-                        } else {
-                            buyer.shares_no += shares_bought;
-                        }
-                        Ok(buyer)
-                    },
-                )?;
-
                 (shares_bought, new_price_yes, new_price_no)
             }
             "NO" => {
@@ -401,37 +824,66 @@ pub mod execute {
                 market.price_yes = new_price_yes;
                 market.price_no = new_price_no;
 
-                // Update buyer's shares
-                BUYERS.update(
-                    deps.storage,
-                    (market_id, info.sender.clone()),
-                    |record| -> StdResult<Buyer> {
-                        let mut buyer = record.unwrap_or(Buyer {
-                            address: info.sender.clone(),
-                            shares_yes: Uint128::zero(),
-                            shares_no: Uint128::zero(),
-                        });
-                        if outcome == "YES" {
-                            buyer.shares_yes += shares_bought;
-                        } else {
-                            buyer.shares_no += shares_bought;
-                        }
-                        Ok(buyer)
-                    },
-                )?;
-
                 (shares_bought, new_price_yes, new_price_no)
             }
 
             _ => return Err(ContractError::Std(StdError::generic_err("Invalid outcome"))),
         };
 
+        // Update buyer's shares
+        let key = (market_id, info.sender.clone());
+        let mut buyer = BUYERS
+            .may_load(deps.storage, key.clone())?
+            .unwrap_or(Buyer {
+                address: info.sender.clone(),
+                shares_yes: Uint128::zero(),
+                shares_no: Uint128::zero(),
+                reverted_cost: Uint128::zero(),
+                reverted_proceeds: Uint128::zero(),
+                spent: Uint128::zero(),
+            });
+        if outcome == "YES" {
+            buyer.shares_yes += shares_bought;
+        } else {
+            buyer.shares_no += shares_bought;
+        }
+        buyer.spent += net_amount;
+        market.open_interest += net_amount;
+        market.collateral += net_amount;
+        ensure_within_limits(market_id, &market, &buyer)?;
+        BUYERS.save(deps.storage, key, &buyer)?;
+
+        market.volume += net_amount;
         MARKETS.save(deps.storage, market_id, &market)?;
+        record_trade(
+            deps.storage,
+            market_id,
+            Trade {
+                trader: info.sender,
+                side: TradeSide::Buy,
+                outcome: outcome.clone(),
+                collateral: net_amount,
+                shares: shares_bought,
+                pool_before,
+                pool_after: (market.shares_yes, market.shares_no),
+                executed_at: env.block.time,
+                reverted: false,
+            },
+        )?;
 
-        Ok(Response::new()
+        let mut response = Response::new();
+        if !fee.is_zero() {
+            response = response.add_message(BankMsg::Send {
+                to_address: config.treasury.to_string(),
+                amount: vec![Coin::new(fee, market.collateral_denom)],
+            });
+        }
+
+        Ok(response
             .add_attribute("action", "buy_shares")
             .add_attribute("market_id", market_id.to_string())
             .add_attribute("outcome", outcome)
+            .add_attribute("fee", fee.to_string())
             .add_attribute("shares_bought", shares_bought.to_string())
             .add_attribute("price_yes", new_price_yes.to_string())
             .add_attribute("price_no", new_price_no.to_string()))
@@ -439,6 +891,7 @@ pub mod execute {
 
     pub fn sell_shares(
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         market_id: u64,
         outcome: String,
@@ -446,11 +899,16 @@ pub mod execute {
     ) -> Result<Response, ContractError> {
         let mut market = MARKETS.load(deps.storage, market_id)?;
 
-        if market.resolved {
-            return Err(ContractError::Std(StdError::generic_err(
-                "Cannot trade in a resolved market",
-            )));
-        }
+        ensure_open(&market, &env, market_id)?;
+        ensure_not_paused(deps.storage, &market, market_id)?;
+        ensure_allowed(
+            deps.as_ref(),
+            &info.sender,
+            ComplianceAction::Sell,
+            market_id,
+        )?;
+        // Selling only exits a position, so it stays open to holders taken off
+        // the participants of a private market
 
         // Check if the user has enough shares to sell
         let mut buyer = BUYERS
@@ -479,52 +937,66 @@ pub mod execute {
             _ => return Err(ContractError::Std(StdError::generic_err("Invalid outcome"))),
         };
 
-        // Constant product invariant
-        let invariant = market.shares_yes * market.shares_no;
-
-        let (usdc_received, new_price_yes, new_price_no) = match outcome.as_str() {
-            "YES" => {
-                let new_shares_yes = market.shares_yes - shares_to_sell;
-                let new_shares_no = invariant / new_shares_yes;
-                let usdc_received = market.shares_no - new_shares_no;
-
-                market.shares_yes = new_shares_yes;
-                market.shares_no = new_shares_no;
-
-                let new_price_yes = calculate_price(market.shares_yes, market.shares_no);
-                let new_price_no = calculate_price(market.shares_no, market.shares_yes);
-
-                (usdc_received, new_price_yes, new_price_no)
-            }
-            "NO" => {
-                let new_shares_no = market.shares_no - shares_to_sell;
-                let new_shares_yes = invariant / new_shares_no;
-                let usdc_received = market.shares_yes - new_shares_yes;
-
-                market.shares_yes = new_shares_yes;
-                market.shares_no = new_shares_no;
-
-                let new_price_yes = calculate_price(market.shares_yes, market.shares_no);
-                let new_price_no = calculate_price(market.shares_no, market.shares_yes);
-
-                (usdc_received, new_price_yes, new_price_no)
-            }
-            _ => return Err(ContractError::Std(StdError::generic_err("Invalid outcome"))),
+        // The sold shares go back to the pool, which then redeems as many full
+        // YES+NO sets as keeps the constant product invariant
+        let pool_before = (market.shares_yes, market.shares_no);
+        let (sold_pool, other_pool) = match outcome.as_str() {
+            "YES" => (market.shares_yes, market.shares_no),
+            _ => (market.shares_no, market.shares_yes),
         };
+        let usdc_received = sell_payout(sold_pool, other_pool, shares_to_sell)?;
+        let new_sold_pool = (sold_pool + shares_to_sell)
+            .checked_sub(usdc_received)
+            .map_err(StdError::from)?;
+        let new_other_pool = other_pool
+            .checked_sub(usdc_received)
+            .map_err(StdError::from)?;
+        if outcome == "YES" {
+            market.shares_yes = new_sold_pool;
+            market.shares_no = new_other_pool;
+        } else {
+            market.shares_yes = new_other_pool;
+            market.shares_no = new_sold_pool;
+        }
+        let new_price_yes = calculate_price(market.shares_yes, market.shares_no);
+        let new_price_no = calculate_price(market.shares_no, market.shares_yes);
 
         // Update buyer's shares
+        buyer.spent = buyer.spent.saturating_sub(usdc_received);
         BUYERS.save(deps.storage, (market_id, info.sender.clone()), &buyer)?;
 
         // Update market prices
         market.price_yes = new_price_yes;
         market.price_no = new_price_no;
 
+        market.volume += usdc_received;
+        market.open_interest = market.open_interest.saturating_sub(usdc_received);
+        market.collateral = market.collateral.saturating_sub(usdc_received);
         MARKETS.save(deps.storage, market_id, &market)?;
+        record_trade(
+            deps.storage,
+            market_id,
+            Trade {
+                trader: info.sender.clone(),
+                side: TradeSide::Sell,
+                outcome: outcome.clone(),
+                collateral: usdc_received,
+                shares: shares_to_sell,
+                pool_before,
+                pool_after: (market.shares_yes, market.shares_no),
+                executed_at: env.block.time,
+                reverted: false,
+            },
+        )?;
 
-        // Send USDC to the seller (pseudo-code, replace with actual token transfer logic)
-        // deps.querier.send_tokens(&info.sender, usdc_received)?;
+        // Send the collateral to the seller
+        let payment = BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(usdc_received, market.collateral_denom)],
+        };
 
         Ok(Response::new()
+            .add_message(payment)
             .add_attribute("action", "sell_shares")
             .add_attribute("market_id", market_id.to_string())
             .add_attribute("outcome", outcome)
@@ -534,127 +1006,3932 @@ pub mod execute {
             .add_attribute("price_no", new_price_no.to_string()))
     }
 
-    /// Resolve a market and distribute payouts
+    /// Resolve a market once trading has closed, as a holder of the Resolver
+    /// role. A creator resolving its own market backs the outcome with the
+    /// creator bond while it can be disputed.
     pub fn resolve_market(
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         market_id: u64,
         winning_outcome: String,
+        evidence: Vec<String>,
+        event_time: Option<Timestamp>,
     ) -> Result<Response, ContractError> {
         let mut market = MARKETS.load(deps.storage, market_id)?;
 
-        if market.resolved {
-            return Err(ContractError::Std(StdError::generic_err(
-                "Market is already resolved",
-            )));
-        }
+        ensure_resolvable(&market, &env, MarketStatus::Proposed)?;
 
-        if info.sender != market.creator {
-            return Err(ContractError::Std(StdError::generic_err(
-                "Only the market creator can resolve the market",
-            )));
+        if market.resolver != Resolver::Creator {
+            return Err(ContractError::ResolverMismatch { market_id });
         }
-
-        market.resolved = true;
-        market.winning_outcome = Some(winning_outcome.clone());
-
-        // Distribute payouts to buyers
-        let buyers = BUYERS
-            .prefix(market_id)
-            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-            .collect::<StdResult<Vec<_>>>()?;
-
-        for (buyer_addr, buyer) in buyers {
-            let payout = if winning_outcome == "YES" {
-                buyer.shares_yes
-            } else {
-                buyer.shares_no
-            };
-
-            // Send payout to the buyer (pseudo-code, replace with actual token transfer logic)
-            // deps.querier.send_tokens(&buyer_addr, payout)?;
+        if !has_role(deps.storage, Role::Resolver, &info.sender)? {
+            return Err(ContractError::Unauthorized {});
         }
 
-        // Distribute fees to liquidity providers
-        let liquidity_providers = LIQUIDITY_PROVIDERS
-            .prefix(market_id)
-            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-            .collect::<StdResult<Vec<_>>>()?;
+        validate_outcome(deps.storage, market_id, &market, &winning_outcome)?;
+        market.event_time = validate_event_time(&env, &market, event_time)?;
 
-        for (provider_addr, provider) in liquidity_providers {
-            // Send payout to the provider (pseudo-code, replace with actual token transfer logic)
-            // deps.querier.send_tokens(&provider_addr, provider.contributed_liquidity)?;
-        }
+        // Other resolvers report without a bond, the creator's stays with the
+        // market and is returned with the claim
+        let bond = if info.sender == market.creator {
+            std::mem::take(&mut market.creator_bond)
+        } else {
+            Uint128::zero()
+        };
+        let proposal = open_proposal(
+            deps.storage,
+            &env,
+            market_id,
+            &mut market,
+            info.sender.clone(),
+            winning_outcome.clone(),
+            bond,
+        )?;
+        log_resolution(
+            deps.storage,
+            &env,
+            market_id,
+            ResolutionAction::Proposed,
+            info.sender,
+            Some(winning_outcome.clone()),
+            evidence,
+        )?;
 
         Ok(Response::new()
             .add_attribute("action", "resolve_market")
             .add_attribute("market_id", market_id.to_string())
-            .add_attribute("winning_outcome", winning_outcome))
+            .add_attribute("winning_outcome", winning_outcome)
+            .add_attribute("status", market.status.to_string())
+            .add_attribute("bond", bond.to_string())
+            .add_attribute("expires_at", proposal.expires_at.seconds().to_string()))
     }
 
-    fn calculate_price(share_pool: Uint128, other_pool: Uint128) -> Uint128 {
-        let total_shares = share_pool + other_pool;
-        if total_shares.is_zero() {
-            return Uint128::zero();
-        }
-        other_pool * Uint128::from(DECIMAL_PRECISION) / total_shares
-    }
-}
+    /// Make the outcome of a resolved market, or of an undisputed proposal whose
+    /// liveness window has passed, final so payouts can be claimed
+    pub fn finalize_market(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        market_id: u64,
+    ) -> Result<Response, ContractError> {
+        let (outcome, payouts, attributes) = finalize(deps.storage, &env, market_id, info.sender)?;
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetCount {} => to_json_binary(&query::count(deps)?),
+        Ok(Response::new()
+            .add_messages(payouts)
+            .add_attribute("action", "finalize_market")
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("winning_outcome", outcome)
+            .add_attributes(attributes))
     }
-}
 
-pub mod query {
-    use super::*;
+    /// Propose the outcome of a closed market by posting the proposal bond.
+    /// The proposal becomes final if nobody disputes it during the liveness window.
+    pub fn propose_outcome(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        market_id: u64,
+        outcome: String,
+        evidence: Vec<String>,
+        event_time: Option<Timestamp>,
+    ) -> Result<Response, ContractError> {
+        let mut market = MARKETS.load(deps.storage, market_id)?;
+        let config = CONFIG.load(deps.storage)?;
 
-    pub fn count(deps: Deps) -> StdResult<GetCountResponse> {
-        let state = STATE.load(deps.storage)?;
-        Ok(GetCountResponse { count: state.count })
+        ensure_resolvable(&market, &env, MarketStatus::Proposed)?;
+        if market.resolver != Resolver::Creator {
+            return Err(ContractError::ResolverMismatch { market_id });
+        }
+        validate_outcome(deps.storage, market_id, &market, &outcome)?;
+        // Only appointed resolvers may have trades reverted
+        if event_time.is_some() && !has_role(deps.storage, Role::Resolver, &info.sender)? {
+            return Err(ContractError::Unauthorized {});
+        }
+        market.event_time = validate_event_time(&env, &market, event_time)?;
+        ensure_funds(&info, &market.collateral_denom, config.proposal_bond)?;
+
+        let proposal = open_proposal(
+            deps.storage,
+            &env,
+            market_id,
+            &mut market,
+            info.sender.clone(),
+            outcome.clone(),
+            config.proposal_bond,
+        )?;
+        log_resolution(
+            deps.storage,
+            &env,
+            market_id,
+            ResolutionAction::Proposed,
+            info.sender,
+            Some(outcome.clone()),
+            evidence,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "propose_outcome")
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("outcome", outcome)
+            .add_attribute("expires_at", proposal.expires_at.seconds().to_string()))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, from_json};
+    /// Open a new dispute round: staking twice the previous round flips the
+    /// tentative outcome and restarts the dispute window
+    pub fn dispute_outcome(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        market_id: u64,
+        outcome: String,
+        evidence: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        let mut market = MARKETS.load(deps.storage, market_id)?;
+        let config = CONFIG.load(deps.storage)?;
 
-    // Helper function to initialize a market
-    fn setup_market(deps: DepsMut) -> u64 {
-        let market_id = 1;
-        let creator = Addr::unchecked("creator");
-        let initial_liquidity = Uint128::new(1000);
+        let status = market.status_at(env.block.time);
+        if status != MarketStatus::Proposed && status != MarketStatus::Disputed {
+            return Err(ContractError::InvalidTransition {
+                from: status,
+                to: MarketStatus::Disputed,
+            });
+        }
 
-        // Create a market
-        let msg = ExecuteMsg::CreateMarket {
-            description: "Will it rain tomorrow?".to_string(),
-            initial_liquidity,
-        };
-        let info = mock_info(creator.as_str(), &coins(1000, "USDC"));
-        execute(deps, mock_env(), info, msg).unwrap();
+        let mut proposal = PROPOSALS.load(deps.storage, market_id)?;
+        if proposal.round >= config.max_dispute_rounds {
+            return Err(ContractError::RoundLimitReached {
+                rounds: config.max_dispute_rounds,
+            });
+        }
+        if env.block.time >= proposal.expires_at {
+            return Err(ContractError::LivenessExpired {
+                expires_at: proposal.expires_at,
+            });
+        }
+        if outcome == proposal.outcome {
+            return Err(ContractError::SameOutcome { outcome });
+        }
+        validate_outcome(deps.storage, market_id, &market, &outcome)?;
 
-        market_id
-    }
+        let previous = DISPUTE_ROUNDS.load(deps.storage, (market_id, proposal.round))?;
+        if info.sender == previous.staker {
+            return Err(ContractError::Unauthorized {});
+        }
+        let stake = (previous.stake + previous.stake).max(config.proposal_bond);
+        ensure_funds(&info, &market.collateral_denom, stake)?;
 
-    #[test]
-    fn test_add_liquidity_to_unresolved_market() {
-        let mut deps = mock_dependencies();
-        let market_id = setup_market(deps.as_mut());
+        proposal.round += 1;
+        proposal.outcome = outcome.clone();
+        proposal.expires_at = env.block.time.plus_seconds(config.liveness);
+        PROPOSALS.save(deps.storage, market_id, &proposal)?;
+        FINALIZE_QUEUE.save(
+            deps.storage,
+            (proposal.expires_at.seconds(), market_id),
+            &Empty {},
+        )?;
+        let dispute = DisputeRound {
+            staker: info.sender.clone(),
+            outcome: outcome.clone(),
+            stake,
+            staked_at: env.block.time,
+        };
+        DISPUTE_ROUNDS.save(deps.storage, (market_id, proposal.round), &dispute)?;
 
-        // Add liquidity to the market
-        let liquidity_provider = Addr::unchecked("provider");
-        let amount = Uint128::new(500);
-        let msg = ExecuteMsg::AddLiquidity { market_id, amount };
-        let info = mock_info(liquidity_provider.as_str(), &coins(500, "USDC"));
-        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        market.status = MarketStatus::Disputed;
+        MARKETS.save(deps.storage, market_id, &market)?;
+        log_resolution(
+            deps.storage,
+            &env,
+            market_id,
+            ResolutionAction::Disputed,
+            info.sender.clone(),
+            Some(outcome.clone()),
+            evidence,
+        )?;
 
-        // Verify the response attributes
-        assert_eq!(
-            res.attributes,
+        Ok(Response::new()
+            .add_attribute("action", "dispute_outcome")
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("disputer", info.sender)
+            .add_attribute("round", proposal.round.to_string())
+            .add_attribute("outcome", outcome)
+            .add_attribute("stake", stake.to_string())
+            .add_attribute("expires_at", proposal.expires_at.seconds().to_string()))
+    }
+
+    /// Settle a dispute that used up its rounds. Stakers on the arbiter's side
+    /// split the losing stakes. The arbiter also has the last word on the
+    /// event time.
+    pub fn arbitrate_dispute(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        market_id: u64,
+        outcome: String,
+        evidence: Vec<String>,
+        event_time: Option<Timestamp>,
+    ) -> Result<Response, ContractError> {
+        let mut market = MARKETS.load(deps.storage, market_id)?;
+        let config = CONFIG.load(deps.storage)?;
+
+        if info.sender != config.arbiter {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let status = market.status_at(env.block.time);
+        if status != MarketStatus::Disputed {
+            return Err(ContractError::InvalidTransition {
+                from: status,
+                to: MarketStatus::Finalized,
+            });
+        }
+
+        let proposal = PROPOSALS.load(deps.storage, market_id)?;
+        if proposal.round < config.max_dispute_rounds {
+            return Err(ContractError::RoundLimitNotReached {
+                rounds: config.max_dispute_rounds,
+            });
+        }
+        validate_outcome(deps.storage, market_id, &market, &outcome)?;
+        market.event_time = validate_event_time(&env, &market, event_time)?;
+
+        let payouts = settle_stakes(deps.storage, market_id, &market, &outcome)?;
+        let attributes = settle_market(deps.storage, market_id, &mut market, outcome.clone())?;
+        log_resolution(
+            deps.storage,
+            &env,
+            market_id,
+            ResolutionAction::Arbitrated,
+            info.sender,
+            Some(outcome.clone()),
+            evidence,
+        )?;
+
+        Ok(Response::new()
+            .add_messages(payouts)
+            .add_attribute("action", "arbitrate_dispute")
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("winning_outcome", outcome)
+            .add_attributes(attributes))
+    }
+
+    /// Committee vote on the outcome. The market resolves as soon as one outcome
+    /// reaches the threshold.
+    pub fn vote_outcome(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        market_id: u64,
+        outcome: String,
+        evidence: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        let mut market = MARKETS.load(deps.storage, market_id)?;
+
+        let (members, threshold) = match &market.resolver {
+            Resolver::Committee {
+                members, threshold, ..
+            } => (members.clone(), *threshold),
+            _ => return Err(ContractError::ResolverMismatch { market_id }),
+        };
+        if !members.contains(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        ensure_resolvable(&market, &env, MarketStatus::Resolved)?;
+        if let Some(deadline) = committee_deadline(&market) {
+            if env.block.time >= deadline {
+                return Err(ContractError::VotingClosed { deadline });
+            }
+        }
+        validate_outcome(deps.storage, market_id, &market, &outcome)?;
+
+        if VOTES.has(deps.storage, (market_id, &info.sender)) {
+            return Err(ContractError::AlreadyVoted { market_id });
+        }
+        VOTES.save(deps.storage, (market_id, &info.sender), &outcome)?;
+        log_resolution(
+            deps.storage,
+            &env,
+            market_id,
+            ResolutionAction::Voted,
+            info.sender.clone(),
+            Some(outcome.clone()),
+            evidence,
+        )?;
+
+        let votes = VOTES
+            .prefix(market_id)
+            .range(deps.storage, None, None, Order::Ascending)
+            .filter(|vote| matches!(vote, Ok((_, voted)) if *voted == outcome))
+            .count() as u32;
+
+        let mut response = Response::new()
+            .add_attribute("action", "vote_outcome")
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("voter", info.sender)
+            .add_attribute("outcome", outcome.clone())
+            .add_attribute("votes", votes.to_string());
+
+        if votes >= threshold {
+            let attributes = record_outcome(deps.storage, &env, market_id, &mut market, outcome)?;
+            response = response
+                .add_attribute("status", market.status.to_string())
+                .add_attributes(attributes);
+        }
+        Ok(response)
+    }
+
+    /// Permissionless recording of the feed prices of a price market inside
+    /// its observation window, for the resolution to use later
+    pub fn observe_price(
+        deps: DepsMut,
+        env: Env,
+        market_id: u64,
+    ) -> Result<Response, ContractError> {
+        let market = MARKETS.load(deps.storage, market_id)?;
+        let Resolver::PriceFeed {
+            asset,
+            observation_time,
+            ..
+        } = &market.resolver
+        else {
+            return Err(ContractError::ResolverMismatch { market_id });
+        };
+
+        let status = market.status_at(env.block.time);
+        if matches!(status, MarketStatus::Voided | MarketStatus::Finalized) {
+            return Err(ContractError::MarketSettled { market_id, status });
+        }
+        if env.block.time < *observation_time {
+            return Err(ContractError::ObservationTooEarly {
+                observation_time: *observation_time,
+            });
+        }
+        if !ORACLE_REPORTS.prefix(market_id).is_empty(deps.storage) {
+            return Err(ContractError::PriceAlreadyObserved { market_id });
+        }
+
+        let reports = observe_prices(deps.as_ref(), &env, asset, *observation_time)?;
+        for (index, report) in reports.iter().enumerate() {
+            ORACLE_REPORTS.save(deps.storage, (market_id, index as u32), report)?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "observe_price")
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("sources", reports.len().to_string()))
+    }
+
+    /// Permissionless resolution of an oracle or price market once its
+    /// resolution time has passed
+    pub fn resolve_from_oracle(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        market_id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut market = MARKETS.load(deps.storage, market_id)?;
+        let config = CONFIG.load(deps.storage)?;
+
+        if !matches!(
+            market.resolver,
+            Resolver::Oracle { .. } | Resolver::PriceFeed { .. }
+        ) {
+            return Err(ContractError::ResolverMismatch { market_id });
+        }
+        ensure_resolvable(&market, &env, MarketStatus::Resolved)?;
+
+        let (outcome, reports, agreed) = match &market.resolver {
+            Resolver::Oracle {
+                sources,
+                tolerance_bps,
+            } => aggregate_answers(deps.as_ref(), &env, sources, *tolerance_bps)?,
+            Resolver::PriceFeed {
+                asset,
+                comparator,
+                threshold,
+                observation_time,
+                tolerance_bps,
+            } => {
+                // Prices recorded inside the observation window come first, the
+                // feeds are only read while that window is open
+                let recorded = ORACLE_REPORTS
+                    .prefix(market_id)
+                    .range(deps.storage, None, None, Order::Ascending)
+                    .map(|item| item.map(|(_, report)| report))
+                    .collect::<StdResult<Vec<_>>>()?;
+                let window_end = observation_time.plus_seconds(config.max_price_age);
+                let reports = if !recorded.is_empty() {
+                    Some(recorded)
+                } else if env.block.time > window_end {
+                    None
+                } else {
+                    Some(observe_prices(
+                        deps.as_ref(),
+                        &env,
+                        asset,
+                        *observation_time,
+                    )?)
+                };
+                match reports {
+                    Some(reports) => {
+                        let (price, agreed) = median_price(&reports, *tolerance_bps)?;
+                        let outcome = if comparator.holds(price, *threshold) {
+                            "YES"
+                        } else {
+                            "NO"
+                        };
+                        (outcome.to_string(), reports, agreed)
+                    }
+                    // Nobody recorded the price while the feeds could show it
+                    None => ("INVALID".to_string(), vec![], true),
+                }
+            }
+            _ => unreachable!(),
+        };
+
+        validate_outcome(deps.storage, market_id, &market, &outcome)?;
+        for (index, report) in reports.iter().enumerate() {
+            ORACLE_REPORTS.save(deps.storage, (market_id, index as u32), report)?;
+        }
+        log_resolution(
+            deps.storage,
+            &env,
+            market_id,
+            ResolutionAction::OracleReported,
+            info.sender,
+            Some(outcome.clone()),
+            vec![],
+        )?;
+
+        // Sources that disagree too much leave the outcome to the dispute flow
+        let attributes = if agreed {
+            record_outcome(deps.storage, &env, market_id, &mut market, outcome.clone())?
+        } else {
+            open_proposal(
+                deps.storage,
+                &env,
+                market_id,
+                &mut market,
+                env.contract.address.clone(),
+                outcome.clone(),
+                Uint128::zero(),
+            )?;
+            market.status = MarketStatus::Disputed;
+            MARKETS.save(deps.storage, market_id, &market)?;
+            vec![]
+        };
+
+        Ok(Response::new()
+            .add_attribute("action", "resolve_from_oracle")
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("sources", reports.len().to_string())
+            .add_attribute("winning_outcome", outcome)
+            .add_attribute("agreed", agreed.to_string())
+            .add_attribute("status", market.status.to_string())
+            .add_attributes(attributes))
+    }
+
+    /// Cancel a market: untouched markets are deleted and refunded, markets with
+    /// activity can only be voided by the owner
+    pub fn cancel_market(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        market_id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut market = MARKETS.load(deps.storage, market_id)?;
+        let config = CONFIG.load(deps.storage)?;
+
+        let status = market.status_at(env.block.time);
+        if matches!(status, MarketStatus::Voided | MarketStatus::Finalized) {
+            return Err(ContractError::InvalidTransition {
+                from: status,
+                to: MarketStatus::Voided,
+            });
+        }
+
+        let is_owner = config.is_owner(&info.sender);
+        if info.sender != market.creator && !is_owner {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        // Untouched means no positions and no liquidity other than the creator's
+        let has_trades = !BUYERS.prefix(market_id).is_empty(deps.storage);
+        let has_outside_liquidity = LIQUIDITY_PROVIDERS
+            .prefix(market_id)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .any(|provider| provider.map_or(true, |address| address != market.creator));
+        let untouched = !has_trades
+            && !has_outside_liquidity
+            && matches!(status, MarketStatus::Open | MarketStatus::Closed);
+
+        if untouched {
+            MARKETS.remove(deps.storage, market_id);
+            LIQUIDITY_PROVIDERS.remove(deps.storage, (market_id, market.creator.clone()));
+            if let Some(condition) = &market.condition {
+                CHILD_MARKETS.remove(deps.storage, (condition.market_id, market_id));
+            }
+            if let Some(group_id) = market.group_id {
+                EVENT_GROUPS.update(deps.storage, group_id, |group| -> StdResult<_> {
+                    let mut group = group.ok_or_else(|| StdError::not_found("EventGroup"))?;
+                    group.market_ids.retain(|id| *id != market_id);
+                    Ok(group)
+                })?;
+            }
+            let voided = void_unmet_children(deps.storage, market_id, None)?;
+            let attributes = voided_attribute(&voided);
+
+            let refund = market.total_liquidity + market.creator_bond + market.spam_deposit;
+            let payment = BankMsg::Send {
+                to_address: market.creator.to_string(),
+                amount: vec![Coin::new(refund, market.collateral_denom)],
+            };
+
+            return Ok(Response::new()
+                .add_message(payment)
+                .add_attribute("action", "cancel_market")
+                .add_attribute("market_id", market_id.to_string())
+                .add_attribute("refund", refund.to_string())
+                .add_attributes(attributes));
+        }
+
+        // Past the first trade the market is voided, which only the owner may do
+        if !is_owner {
+            return Err(ContractError::MarketHasActivity { market_id });
+        }
+
+        let attributes =
+            settle_market(deps.storage, market_id, &mut market, "INVALID".to_string())?;
+        log_resolution(
+            deps.storage,
+            &env,
+            market_id,
+            ResolutionAction::Cancelled,
+            info.sender,
+            Some("INVALID".to_string()),
+            vec![],
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "cancel_market")
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("status", MarketStatus::Voided.to_string())
+            .add_attributes(attributes))
+    }
+
+    /// Move outcome shares to another address while the market is unsettled
+    pub fn transfer_shares(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        market_id: u64,
+        recipient: String,
+        outcome: String,
+        amount: Uint128,
+    ) -> Result<Response, ContractError> {
+        let market = MARKETS.load(deps.storage, market_id)?;
+        let recipient = deps.api.addr_validate(&recipient)?;
+        if recipient == info.sender {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Cannot transfer shares to yourself",
+            )));
+        }
+
+        let status = market.status_at(env.block.time);
+        if matches!(status, MarketStatus::Voided | MarketStatus::Finalized) {
+            return Err(ContractError::MarketSettled { market_id, status });
+        }
+        ensure_not_paused(deps.storage, &market, market_id)?;
+        for address in [&info.sender, &recipient] {
+            ensure_allowed(
+                deps.as_ref(),
+                address,
+                ComplianceAction::TransferShares,
+                market_id,
+            )?;
+            ensure_participant(deps.storage, &market, market_id, address)?;
+        }
+
+        if amount.is_zero() {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Amount must be greater than zero",
+            )));
+        }
+
+        let mut sender = BUYERS
+            .may_load(deps.storage, (market_id, info.sender.clone()))?
+            .unwrap_or(Buyer {
+                address: info.sender.clone(),
+                shares_yes: Uint128::zero(),
+                shares_no: Uint128::zero(),
+                reverted_cost: Uint128::zero(),
+                reverted_proceeds: Uint128::zero(),
+                spent: Uint128::zero(),
+            });
+        let mut receiver = BUYERS
+            .may_load(deps.storage, (market_id, recipient.clone()))?
+            .unwrap_or(Buyer {
+                address: recipient.clone(),
+                shares_yes: Uint128::zero(),
+                shares_no: Uint128::zero(),
+                reverted_cost: Uint128::zero(),
+                reverted_proceeds: Uint128::zero(),
+                spent: Uint128::zero(),
+            });
+        let held = sender.shares_yes + sender.shares_no;
+        let (from, to) = match outcome.as_str() {
+            "YES" => (&mut sender.shares_yes, &mut receiver.shares_yes),
+            "NO" => (&mut sender.shares_no, &mut receiver.shares_no),
+            _ => return Err(ContractError::InvalidOutcome { outcome }),
+        };
+        if *from < amount {
+            return Err(ContractError::InsufficientShares {
+                available: *from,
+                required: amount,
+            });
+        }
+        *from -= amount;
+        *to += amount;
+
+        // The collateral spent on the shares moves with them
+        let cost = sender.spent.multiply_ratio(amount, held);
+        sender.spent -= cost;
+        receiver.spent += cost;
+        ensure_within_limits(market_id, &market, &receiver)?;
+
+        BUYERS.save(deps.storage, (market_id, info.sender.clone()), &sender)?;
+        BUYERS.save(deps.storage, (market_id, recipient.clone()), &receiver)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "transfer_shares")
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("recipient", recipient)
+            .add_attribute("outcome", outcome)
+            .add_attribute("amount", amount.to_string()))
+    }
+
+    /// Merge a full set of conditional shares back into the parent outcome shares
+    /// the market is conditional on, like merging positions of conditional tokens
+    pub fn merge_positions(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        market_id: u64,
+        amount: Uint128,
+    ) -> Result<Response, ContractError> {
+        let mut market = MARKETS.load(deps.storage, market_id)?;
+        let condition = market
+            .condition
+            .clone()
+            .ok_or(ContractError::NotConditional { market_id })?;
+
+        let status = market.status_at(env.block.time);
+        if matches!(status, MarketStatus::Voided | MarketStatus::Finalized) {
+            return Err(ContractError::MarketSettled { market_id, status });
+        }
+        ensure_not_paused(deps.storage, &market, market_id)?;
+
+        if amount.is_zero() {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Amount must be greater than zero",
+            )));
+        }
+
+        let mut buyer = BUYERS
+            .may_load(deps.storage, (market_id, info.sender.clone()))?
+            .unwrap_or(Buyer {
+                address: info.sender.clone(),
+                shares_yes: Uint128::zero(),
+                shares_no: Uint128::zero(),
+                reverted_cost: Uint128::zero(),
+                reverted_proceeds: Uint128::zero(),
+                spent: Uint128::zero(),
+            });
+        let available = buyer.shares_yes.min(buyer.shares_no);
+        if available < amount {
+            return Err(ContractError::InsufficientShares {
+                available,
+                required: amount,
+            });
+        }
+        let held = buyer.shares_yes + buyer.shares_no;
+        buyer.shares_yes -= amount;
+        buyer.shares_no -= amount;
+        let cost = buyer.spent.multiply_ratio(amount * Uint128::new(2), held);
+        buyer.spent -= cost;
+
+        let mut parent = MARKETS.load(deps.storage, condition.market_id)?;
+        ensure_not_paused(deps.storage, &parent, condition.market_id)?;
+        // Merging buys into the parent as much as a trade does
+        ensure_allowed(
+            deps.as_ref(),
+            &info.sender,
+            ComplianceAction::Buy,
+            condition.market_id,
+        )?;
+        ensure_participant(deps.storage, &parent, condition.market_id, &info.sender)?;
+        let mut parent_buyer = BUYERS
+            .may_load(deps.storage, (condition.market_id, info.sender.clone()))?
+            .unwrap_or(Buyer {
+                address: info.sender.clone(),
+                shares_yes: Uint128::zero(),
+                shares_no: Uint128::zero(),
+                reverted_cost: Uint128::zero(),
+                reverted_proceeds: Uint128::zero(),
+                spent: Uint128::zero(),
+            });
+        if condition.outcome == "YES" {
+            parent_buyer.shares_yes += amount;
+        } else {
+            parent_buyer.shares_no += amount;
+        }
+        parent_buyer.spent += amount;
+
+        // The collateral behind the merged sets now backs the parent shares
+        market.collateral = market.collateral.saturating_sub(amount);
+        market.open_interest = market.open_interest.saturating_sub(amount);
+        parent.collateral += amount;
+        parent.open_interest += amount;
+        ensure_within_limits(condition.market_id, &parent, &parent_buyer)?;
+
+        BUYERS.save(deps.storage, (market_id, info.sender.clone()), &buyer)?;
+        BUYERS.save(
+            deps.storage,
+            (condition.market_id, info.sender.clone()),
+            &parent_buyer,
+        )?;
+        MARKETS.save(deps.storage, market_id, &market)?;
+        MARKETS.save(deps.storage, condition.market_id, &parent)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "merge_positions")
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("parent_market_id", condition.market_id.to_string())
+            .add_attribute("outcome", condition.outcome)
+            .add_attribute("amount", amount.to_string()))
+    }
+
+    /// Tie open markets of the sender together as mutually exclusive outcomes
+    pub fn create_event_group(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        description: String,
+        market_ids: Vec<u64>,
+    ) -> Result<Response, ContractError> {
+        let mut unique_ids = market_ids.clone();
+        unique_ids.sort_unstable();
+        unique_ids.dedup();
+        if unique_ids.len() < 2 || unique_ids.len() != market_ids.len() {
+            return Err(ContractError::InvalidEventGroup {});
+        }
+
+        let group_id = EVENT_GROUP_COUNT.may_load(deps.storage)?.unwrap_or(0) + 1;
+
+        let mut collateral_denom: Option<String> = None;
+        for market_id in &market_ids {
+            let mut market = MARKETS.load(deps.storage, *market_id)?;
+            if market.creator != info.sender {
+                return Err(ContractError::Unauthorized {});
+            }
+            ensure_open(&market, &env, *market_id)?;
+            if let Some(group_id) = market.group_id {
+                return Err(ContractError::MarketAlreadyGrouped {
+                    market_id: *market_id,
+                    group_id,
+                });
+            }
+            match &collateral_denom {
+                Some(denom) if *denom != market.collateral_denom => {
+                    return Err(ContractError::CollateralMismatch {
+                        denom: market.collateral_denom,
+                        expected: denom.clone(),
+                    });
+                }
+                Some(_) => {}
+                None => collateral_denom = Some(market.collateral_denom.clone()),
+            }
+
+            market.group_id = Some(group_id);
+            MARKETS.save(deps.storage, *market_id, &market)?;
+        }
+
+        let group = EventGroup {
+            creator: info.sender,
+            description,
+            market_ids,
+            collateral_denom: collateral_denom.unwrap_or_default(),
+        };
+        EVENT_GROUPS.save(deps.storage, group_id, &group)?;
+        EVENT_GROUP_COUNT.save(deps.storage, &group_id)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "create_event_group")
+            .add_attribute("group_id", group_id.to_string()))
+    }
+
+    /// Convert NO shares in k markets of a group into k - 1 units of collateral and
+    /// YES shares in the remaining markets. Both sides pay the same in every outcome
+    /// where exactly one market of the group resolves YES.
+    pub fn convert_no_positions(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        group_id: u64,
+        market_ids: Vec<u64>,
+        amount: Uint128,
+    ) -> Result<Response, ContractError> {
+        let group = EVENT_GROUPS.load(deps.storage, group_id)?;
+
+        if amount.is_zero() {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Amount must be greater than zero",
+            )));
+        }
+
+        let mut unique_ids = market_ids.clone();
+        unique_ids.sort_unstable();
+        unique_ids.dedup();
+        if unique_ids.is_empty() || unique_ids.len() != market_ids.len() {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Markets to convert must be distinct and not empty",
+            )));
+        }
+        if let Some(market_id) = market_ids
+            .iter()
+            .find(|market_id| !group.market_ids.contains(market_id))
+        {
+            return Err(ContractError::MarketNotInGroup {
+                market_id: *market_id,
+                group_id,
+            });
+        }
+
+        // Every market of the group must still be waiting for its outcome, and
+        // the sender must be let into the markets it gets YES in
+        for market_id in &group.market_ids {
+            let market = MARKETS.load(deps.storage, *market_id)?;
+            let status = market.status_at(env.block.time);
+            if !matches!(status, MarketStatus::Open | MarketStatus::Closed) {
+                return Err(ContractError::MarketSettled {
+                    market_id: *market_id,
+                    status,
+                });
+            }
+            ensure_not_paused(deps.storage, &market, *market_id)?;
+            if market_ids.contains(market_id) {
+                ensure_allowed(
+                    deps.as_ref(),
+                    &info.sender,
+                    ComplianceAction::Sell,
+                    *market_id,
+                )?;
+            } else {
+                ensure_allowed(
+                    deps.as_ref(),
+                    &info.sender,
+                    ComplianceAction::Buy,
+                    *market_id,
+                )?;
+                ensure_participant(deps.storage, &market, *market_id, &info.sender)?;
+            }
+        }
+
+        // The converted markets keep their collateral, which their YES holders
+        // may still claim. The YES handed out in the other markets is backed by
+        // the group instead: at most one market resolves YES, so at least k - 1
+        // of the converted markets end up holding the NO collateral unclaimed,
+        // and all k do when one of the other markets wins.
+        for market_id in &group.market_ids {
+            let key = (*market_id, info.sender.clone());
+            let mut market = MARKETS.load(deps.storage, *market_id)?;
+            let mut buyer = BUYERS
+                .may_load(deps.storage, key.clone())?
+                .unwrap_or(Buyer {
+                    address: info.sender.clone(),
+                    shares_yes: Uint128::zero(),
+                    shares_no: Uint128::zero(),
+                    reverted_cost: Uint128::zero(),
+                    reverted_proceeds: Uint128::zero(),
+                    spent: Uint128::zero(),
+                });
+            if market_ids.contains(market_id) {
+                if buyer.shares_no < amount {
+                    return Err(ContractError::InsufficientShares {
+                        available: buyer.shares_no,
+                        required: amount,
+                    });
+                }
+                buyer.shares_no -= amount;
+                market.open_interest = market.open_interest.saturating_sub(amount);
+            } else {
+                buyer.shares_yes += amount;
+                market.open_interest += amount;
+                ensure_within_limits(*market_id, &market, &buyer)?;
+            }
+            BUYERS.save(deps.storage, key, &buyer)?;
+            MARKETS.save(deps.storage, *market_id, &market)?;
+        }
+
+        let collateral_out = amount * Uint128::from(market_ids.len() as u128 - 1);
+
+        let mut response = Response::new()
+            .add_attribute("action", "convert_no_positions")
+            .add_attribute("group_id", group_id.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("collateral_out", collateral_out.to_string());
+        if !collateral_out.is_zero() {
+            response = response.add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: vec![Coin::new(collateral_out, group.collateral_denom)],
+            });
+        }
+        Ok(response)
+    }
+
+    /// Pay out the sender's outcome shares and their share of the pool
+    pub fn claim(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        market_id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut market = MARKETS.load(deps.storage, market_id)?;
+
+        let (rate_yes, rate_no) = payout_rates(&market).ok_or(ContractError::MarketNotSettled {
+            market_id,
+            status: market.status_at(env.block.time),
+        })?;
+
+        let (shares_payout, pool_payout, bond_refund) = take_payout(
+            deps.storage,
+            market_id,
+            &mut market,
+            (rate_yes, rate_no),
+            &info.sender,
+        )?;
+
+        let payout = shares_payout + pool_payout + bond_refund;
+        if payout.is_zero() {
+            return Err(ContractError::NothingToClaim {});
+        }
+
+        let payment = BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(payout, market.collateral_denom)],
+        };
+
+        Ok(Response::new()
+            .add_message(payment)
+            .add_attribute("action", "claim")
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("shares_payout", shares_payout.to_string())
+            .add_attribute("pool_payout", pool_payout.to_string())
+            .add_attribute("bond_refund", bond_refund.to_string()))
+    }
+
+    /// Add the attached funds to the keeper pool of their denom
+    pub fn fund_keeper_pool(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+        let funds = one_coin(&info)?;
+        let balance = KEEPER_POOL
+            .may_load(deps.storage, &funds.denom)?
+            .unwrap_or_default()
+            + funds.amount;
+        KEEPER_POOL.save(deps.storage, &funds.denom, &balance)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "fund_keeper_pool")
+            .add_attribute("denom", funds.denom)
+            .add_attribute("amount", funds.amount.to_string())
+            .add_attribute("balance", balance.to_string()))
+    }
+
+    /// Change the contract settings, owner only
+    pub fn update_config(
+        deps: DepsMut,
+        info: MessageInfo,
+        msg: UpdateConfigMsg,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        // Fee managers may only touch the fee settings
+        let fee_only = UpdateConfigMsg {
+            trading_fee_bps: None,
+            keeper_reward: None,
+            ..msg.clone()
+        } == UpdateConfigMsg::default();
+        let is_fee_manager = fee_only && has_role(deps.storage, Role::FeeManager, &info.sender)?;
+        if !config.is_owner(&info.sender) && !is_fee_manager {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if let Some(arbiter) = msg.arbiter {
+            config.arbiter = deps.api.addr_validate(&arbiter)?;
+        }
+        if let Some(treasury) = msg.treasury {
+            config.treasury = deps.api.addr_validate(&treasury)?;
+        }
+        if let Some(price_feeds) = msg.price_feeds {
+            config.price_feeds = price_feeds
+                .iter()
+                .map(|feed| deps.api.addr_validate(feed))
+                .collect::<StdResult<_>>()?;
+        }
+        config.proposal_bond = msg.proposal_bond.unwrap_or(config.proposal_bond);
+        config.liveness = msg.liveness.unwrap_or(config.liveness);
+        config.max_price_age = msg.max_price_age.unwrap_or(config.max_price_age);
+        config.max_confidence_bps = msg.max_confidence_bps.unwrap_or(config.max_confidence_bps);
+        config.max_dispute_rounds = msg.max_dispute_rounds.unwrap_or(config.max_dispute_rounds);
+        config.creator_bond = msg.creator_bond.unwrap_or(config.creator_bond);
+        config.slash_treasury_bps = msg.slash_treasury_bps.unwrap_or(config.slash_treasury_bps);
+        config.keeper_reward = msg.keeper_reward.unwrap_or(config.keeper_reward);
+        config.default_collateral = msg.default_collateral.unwrap_or(config.default_collateral);
+        config.trading_fee_bps = msg.trading_fee_bps.unwrap_or(config.trading_fee_bps);
+        config.min_liquidity = msg.min_liquidity.unwrap_or(config.min_liquidity);
+        config.max_liquidity = msg.max_liquidity.unwrap_or(config.max_liquidity);
+        config.collateral_min_liquidity = msg
+            .collateral_min_liquidity
+            .unwrap_or(config.collateral_min_liquidity);
+        config.creation_fee = msg.creation_fee.unwrap_or(config.creation_fee);
+        config.spam_deposit = msg.spam_deposit.unwrap_or(config.spam_deposit);
+        config.min_activity = msg.min_activity.unwrap_or(config.min_activity);
+        config.open_market_creation = msg
+            .open_market_creation
+            .unwrap_or(config.open_market_creation);
+        if let Some(compliance) = msg.compliance {
+            config.compliance = match compliance.as_str() {
+                "" => None,
+                _ => Some(deps.api.addr_validate(&compliance)?),
+            };
+        }
+
+        validate_config(&config)?;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new().add_attribute("action", "update_config"))
+    }
+
+    /// Offer ownership to a new address. Nothing changes until it accepts, so a
+    /// wrong address only leaves an offer to replace.
+    pub fn propose_new_owner(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        owner: String,
+        expiry: Option<Timestamp>,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        if !config.is_owner(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        if let Some(expiry) = expiry {
+            if expiry <= env.block.time {
+                return Err(ContractError::OwnershipTransferExpired { expiry });
+            }
+        }
+
+        let pending = PendingOwner {
+            owner: deps.api.addr_validate(&owner)?,
+            expiry,
+        };
+        PENDING_OWNER.save(deps.storage, &pending)?;
+
+        let expiry = expiry.map_or("none".to_string(), |expiry| expiry.seconds().to_string());
+        Ok(Response::new()
+            .add_attribute("action", "propose_new_owner")
+            .add_event(
+                Event::new("ownership_proposed")
+                    .add_attribute("owner", info.sender)
+                    .add_attribute("pending_owner", pending.owner)
+                    .add_attribute("expiry", expiry),
+            ))
+    }
+
+    /// Take over ownership offered to the sender
+    pub fn accept_ownership(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        let pending = PENDING_OWNER
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoPendingOwner {})?;
+        if info.sender != pending.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        if let Some(expiry) = pending.expiry {
+            if env.block.time >= expiry {
+                return Err(ContractError::OwnershipTransferExpired { expiry });
+            }
+        }
+
+        let mut config = CONFIG.load(deps.storage)?;
+        let previous = config.owner.replace(pending.owner.clone());
+        CONFIG.save(deps.storage, &config)?;
+        PENDING_OWNER.remove(deps.storage);
+
+        Ok(Response::new()
+            .add_attribute("action", "accept_ownership")
+            .add_event(
+                Event::new("ownership_accepted")
+                    .add_attribute(
+                        "previous_owner",
+                        previous.map_or("none".to_string(), String::from),
+                    )
+                    .add_attribute("owner", pending.owner),
+            ))
+    }
+
+    /// Give up ownership for good, along with any pending transfer
+    pub fn renounce_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        if !config.is_owner(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        config.owner = None;
+        CONFIG.save(deps.storage, &config)?;
+        PENDING_OWNER.remove(deps.storage);
+
+        Ok(Response::new()
+            .add_attribute("action", "renounce_ownership")
+            .add_event(
+                Event::new("ownership_renounced").add_attribute("previous_owner", info.sender),
+            ))
+    }
+
+    /// Switch the pause of one market, or the global one, on or off. Open to
+    /// the owner and pausers.
+    pub fn set_paused(
+        deps: DepsMut,
+        info: MessageInfo,
+        market_id: Option<u64>,
+        paused: bool,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        if !config.is_owner(&info.sender) && !has_role(deps.storage, Role::Pauser, &info.sender)? {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        match market_id {
+            Some(market_id) => {
+                let mut market = MARKETS.load(deps.storage, market_id)?;
+                market.paused = paused;
+                MARKETS.save(deps.storage, market_id, &market)?;
+            }
+            None => {
+                config.paused = paused;
+                CONFIG.save(deps.storage, &config)?;
+            }
+        }
+
+        let scope = market_id.map_or("all".to_string(), |market_id| market_id.to_string());
+        Ok(Response::new()
+            .add_attribute("action", if paused { "pause" } else { "unpause" })
+            .add_attribute("market_id", scope))
+    }
+
+    /// Freeze markets at their last prices and void them, owner only. Holders
+    /// redeem their shares at those prices and LPs withdraw their pro-rata share
+    /// of the pool through Claim, with no resolution needed. Should the collateral
+    /// held not cover the exit, the prices are scaled down until it does.
+    pub fn emergency_shutdown(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        market_id: Option<u64>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        if !config.is_owner(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let market_ids = match market_id {
+            Some(market_id) => {
+                let market = MARKETS.load(deps.storage, market_id)?;
+                let status = market.status_at(env.block.time);
+                if matches!(status, MarketStatus::Voided | MarketStatus::Finalized) {
+                    return Err(ContractError::MarketSettled { market_id, status });
+                }
+                vec![market_id]
+            }
+            None => {
+                let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+                MARKETS
+                    .keys(
+                        deps.storage,
+                        start_after.map(Bound::exclusive),
+                        None,
+                        Order::Ascending,
+                    )
+                    .take(limit)
+                    .collect::<StdResult<_>>()?
+            }
+        };
+
+        let mut refunds = vec![];
+        let mut shut_down = vec![];
+        let mut attributes = vec![];
+        for market_id in &market_ids {
+            let market_id = *market_id;
+            let mut market = MARKETS.load(deps.storage, market_id)?;
+            if matches!(
+                market.status,
+                MarketStatus::Voided | MarketStatus::Finalized
+            ) {
+                continue;
+            }
+
+            // Stakes on a pending resolution go back to their stakers
+            let rounds = DISPUTE_ROUNDS
+                .prefix(market_id)
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|item| item.map(|(_, round)| round))
+                .collect::<StdResult<Vec<_>>>()?;
+            for round in rounds.into_iter().filter(|round| !round.stake.is_zero()) {
+                refunds.push(BankMsg::Send {
+                    to_address: round.staker.to_string(),
+                    amount: vec![Coin::new(round.stake, market.collateral_denom.clone())],
+                });
+            }
+
+            // Holders are owed their shares and LPs the pool, both at the last
+            // prices, out of the collateral this market holds and no other's
+            let (rate_yes, rate_no) = (market.price_yes, market.price_no);
+            let held = BUYERS
+                .prefix(market_id)
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|item| {
+                    item.map(|(_, buyer)| {
+                        shares_value(buyer.shares_yes, buyer.shares_no, rate_yes, rate_no)
+                    })
+                })
+                .sum::<StdResult<Uint128>>()?;
+            let owed = held + shares_value(market.shares_yes, market.shares_no, rate_yes, rate_no);
+            if owed > market.collateral {
+                market.price_yes = market.price_yes.multiply_ratio(market.collateral, owed);
+                market.price_no = market.price_no.multiply_ratio(market.collateral, owed);
+            }
+
+            // Frozen as is: no trade gets reverted and the creator keeps the deposit
+            market.event_time = None;
+            market.void_fallback = VoidFallback::LastTradedPrices;
+            market.shutdown = true;
+            // A child may already be voided along with its parent
+            if market.status == MarketStatus::Voided {
+                MARKETS.save(deps.storage, market_id, &market)?;
+            } else {
+                attributes.extend(settle_market(
+                    deps.storage,
+                    market_id,
+                    &mut market,
+                    "INVALID".to_string(),
+                )?);
+            }
+            log_resolution(
+                deps.storage,
+                &env,
+                market_id,
+                ResolutionAction::Shutdown,
+                info.sender.clone(),
+                Some("INVALID".to_string()),
+                vec![],
+            )?;
+            shut_down.push(market_id.to_string());
+        }
+
+        let scope = market_id.map_or("all".to_string(), |market_id| market_id.to_string());
+        let mut response = Response::new()
+            .add_messages(refunds)
+            .add_attribute("action", "emergency_shutdown")
+            .add_attribute("market_id", scope)
+            .add_attribute("shut_down", shut_down.join(","));
+        // Where the next page of markets starts
+        if market_id.is_none() {
+            if let Some(last) = market_ids.last() {
+                response = response.add_attribute("last_market_id", last.to_string());
+            }
+        }
+        Ok(response.add_attributes(attributes))
+    }
+
+    /// Replace the position and open interest caps of a market, owner only
+    pub fn set_market_limits(
+        deps: DepsMut,
+        info: MessageInfo,
+        market_id: u64,
+        max_position: Option<PositionLimit>,
+        max_open_interest: Option<Uint128>,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        if !config.is_owner(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let mut market = MARKETS.load(deps.storage, market_id)?;
+        market.max_position = max_position;
+        market.max_open_interest = max_open_interest;
+        MARKETS.save(deps.storage, market_id, &market)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_market_limits")
+            .add_attribute("market_id", market_id.to_string()))
+    }
+
+    /// Add or remove participants of a private market, creator only
+    pub fn update_participants(
+        deps: DepsMut,
+        info: MessageInfo,
+        market_id: u64,
+        addresses: Vec<String>,
+        added: bool,
+    ) -> Result<Response, ContractError> {
+        let market = MARKETS.load(deps.storage, market_id)?;
+        if info.sender != market.creator {
+            return Err(ContractError::Unauthorized {});
+        }
+        if market.visibility != Visibility::Private {
+            return Err(ContractError::MarketNotPrivate { market_id });
+        }
+
+        for address in &addresses {
+            let address = deps.api.addr_validate(address)?;
+            if added {
+                PARTICIPANTS.save(deps.storage, (market_id, &address), &Empty {})?;
+            } else {
+                PARTICIPANTS.remove(deps.storage, (market_id, &address));
+            }
+        }
+
+        let action = if added {
+            "add_participants"
+        } else {
+            "remove_participants"
+        };
+        Ok(Response::new()
+            .add_attribute("action", action)
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("addresses", addresses.join(",")))
+    }
+
+    /// Grant or revoke a role, owner only
+    pub fn update_role(
+        deps: DepsMut,
+        info: MessageInfo,
+        role: Role,
+        address: String,
+        granted: bool,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        if !config.is_owner(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let address = deps.api.addr_validate(&address)?;
+        let action = if granted {
+            ROLES.save(deps.storage, (role.as_str(), &address), &Empty {})?;
+            "grant_role"
+        } else {
+            ROLES.remove(deps.storage, (role.as_str(), &address));
+            "revoke_role"
+        };
+
+        Ok(Response::new()
+            .add_attribute("action", action)
+            .add_attribute("role", role.as_str())
+            .add_attribute("address", address))
+    }
+
+    fn has_role(storage: &dyn Storage, role: Role, address: &Addr) -> StdResult<bool> {
+        Ok(ROLES.has(storage, (role.as_str(), address)))
+    }
+
+    /// Amount set for `denom` in a per-denom list, zero if it has none
+    fn amount_in(amounts: &[Coin], denom: &str) -> Uint128 {
+        amounts
+            .iter()
+            .find(|coin| coin.denom == denom)
+            .map_or(Uint128::zero(), |coin| coin.amount)
+    }
+
+    /// Store the Closed status of up to `limit` open markets past their close time
+    pub fn close_expired(
+        deps: DepsMut,
+        env: Env,
+        keeper: Option<Addr>,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let due = CLOSE_QUEUE
+            .keys(
+                deps.storage,
+                None,
+                Some(Bound::inclusive((env.block.time.seconds(), u64::MAX))),
+                Order::Ascending,
+            )
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut closed = vec![];
+        let mut denoms = vec![];
+        for (close_at, market_id) in due {
+            CLOSE_QUEUE.remove(deps.storage, (close_at, market_id));
+            // Cancelled markets and markets already moved on just leave the queue
+            let Some(mut market) = MARKETS.may_load(deps.storage, market_id)? else {
+                continue;
+            };
+            if market.status != MarketStatus::Open {
+                continue;
+            }
+            market.status = MarketStatus::Closed;
+            MARKETS.save(deps.storage, market_id, &market)?;
+            closed.push(market_id.to_string());
+            denoms.push(market.collateral_denom);
+        }
+
+        let rewards = keeper_rewards(deps.storage, keeper.as_ref(), denoms)?;
+
+        Ok(Response::new()
+            .add_messages(rewards)
+            .add_attribute("action", "close_expired")
+            .add_attribute("closed", closed.join(",")))
+    }
+
+    /// Finalize up to `limit` markets whose outcome has become final, as
+    /// FinalizeMarket would
+    pub fn finalize_resolved(
+        deps: DepsMut,
+        env: Env,
+        keeper: Option<Addr>,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let due = FINALIZE_QUEUE
+            .keys(
+                deps.storage,
+                None,
+                Some(Bound::inclusive((env.block.time.seconds(), u64::MAX))),
+                Order::Ascending,
+            )
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        let actor = keeper.clone().unwrap_or(env.contract.address.clone());
+
+        let mut payouts = vec![];
+        let mut attributes = vec![];
+        let mut finalized = vec![];
+        let mut denoms = vec![];
+        for (ready_at, market_id) in due {
+            FINALIZE_QUEUE.remove(deps.storage, (ready_at, market_id));
+            let Some(market) = MARKETS.may_load(deps.storage, market_id)? else {
+                continue;
+            };
+            // Entries outdated by a dispute, a finalization or an arbiter
+            // still to decide are dropped
+            match finalize(deps.storage, &env, market_id, actor.clone()) {
+                Ok((_, messages, settled)) => {
+                    payouts.extend(messages);
+                    attributes.extend(settled);
+                    finalized.push(market_id.to_string());
+                    denoms.push(market.collateral_denom);
+                }
+                Err(
+                    ContractError::InvalidTransition { .. }
+                    | ContractError::LivenessNotExpired { .. },
+                ) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+
+        let rewards = keeper_rewards(deps.storage, keeper.as_ref(), denoms)?;
+
+        Ok(Response::new()
+            .add_messages(payouts)
+            .add_messages(rewards)
+            .add_attribute("action", "finalize_resolved")
+            .add_attribute("finalized", finalized.join(","))
+            .add_attributes(attributes))
+    }
+
+    /// Pay out up to `limit` holders of a finalized or voided market, as if
+    /// each of them had claimed
+    pub fn process_claims(
+        deps: DepsMut,
+        env: Env,
+        keeper: Option<Addr>,
+        market_id: u64,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let mut market = MARKETS.load(deps.storage, market_id)?;
+        let rates = payout_rates(&market).ok_or(ContractError::MarketNotSettled {
+            market_id,
+            status: market.status_at(env.block.time),
+        })?;
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let mut holders = BUYERS
+            .prefix(market_id)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        for provider in
+            LIQUIDITY_PROVIDERS
+                .prefix(market_id)
+                .keys(deps.storage, None, None, Order::Ascending)
+        {
+            if holders.len() >= limit {
+                break;
+            }
+            let provider = provider?;
+            if !holders.contains(&provider) {
+                holders.push(provider);
+            }
+        }
+        if holders.is_empty() {
+            return Err(ContractError::NothingToClaim {});
+        }
+
+        let mut payments = vec![];
+        let mut denoms = vec![];
+        for holder in &holders {
+            let (shares_payout, pool_payout, bond_refund) =
+                take_payout(deps.storage, market_id, &mut market, rates, holder)?;
+            let payout = shares_payout + pool_payout + bond_refund;
+            if !payout.is_zero() {
+                payments.push(BankMsg::Send {
+                    to_address: holder.to_string(),
+                    amount: vec![Coin::new(payout, market.collateral_denom.clone())],
+                });
+            }
+            denoms.push(market.collateral_denom.clone());
+        }
+
+        let rewards = keeper_rewards(deps.storage, keeper.as_ref(), denoms)?;
+
+        Ok(Response::new()
+            .add_messages(payments)
+            .add_messages(rewards)
+            .add_attribute("action", "process_claims")
+            .add_attribute("market_id", market_id.to_string())
+            .add_attribute("processed", holders.len().to_string()))
+    }
+
+    /// Reward of a keeper for one processed item per entry of `denoms`, paid
+    /// from the keeper pool of each denom while it lasts
+    fn keeper_rewards(
+        storage: &mut dyn Storage,
+        keeper: Option<&Addr>,
+        denoms: Vec<String>,
+    ) -> StdResult<Vec<BankMsg>> {
+        let Some(keeper) = keeper else {
+            return Ok(vec![]);
+        };
+        let config = CONFIG.load(storage)?;
+
+        let mut rewards: Vec<Coin> = vec![];
+        for denom in denoms {
+            let balance = KEEPER_POOL.may_load(storage, &denom)?.unwrap_or_default();
+            let reward = balance.min(config.keeper_reward);
+            if reward.is_zero() {
+                continue;
+            }
+            KEEPER_POOL.save(storage, &denom, &(balance - reward))?;
+            match rewards.iter_mut().find(|coin| coin.denom == denom) {
+                Some(coin) => coin.amount += reward,
+                None => rewards.push(Coin::new(reward, denom)),
+            }
+        }
+        if rewards.is_empty() {
+            return Ok(vec![]);
+        }
+        rewards.sort_by(|a, b| a.denom.cmp(&b.denom));
+
+        Ok(vec![BankMsg::Send {
+            to_address: keeper.to_string(),
+            amount: rewards,
+        }])
+    }
+
+    /// Removes the position of `holder` in a settled market and returns what it
+    /// is owed: the value of its shares, its share of the pool and, for the
+    /// creator, a bond never put behind a resolution
+    fn take_payout(
+        storage: &mut dyn Storage,
+        market_id: u64,
+        market: &mut Market,
+        (rate_yes, rate_no): (Uint128, Uint128),
+        holder: &Addr,
+    ) -> StdResult<(Uint128, Uint128, Uint128)> {
+        // Shares held directly by the holder, with the net cost of reverted trades
+        let shares_payout = match BUYERS.may_load(storage, (market_id, holder.clone()))? {
+            Some(buyer) => {
+                BUYERS.remove(storage, (market_id, holder.clone()));
+                (shares_value(buyer.shares_yes, buyer.shares_no, rate_yes, rate_no)
+                    + buyer.reverted_cost)
+                    .saturating_sub(buyer.reverted_proceeds)
+            }
+            None => Uint128::zero(),
+        };
+
+        // Pro-rata share of the shares left in the pool
+        let pool_payout =
+            match LIQUIDITY_PROVIDERS.may_load(storage, (market_id, holder.clone()))? {
+                Some(provider) if !market.total_liquidity.is_zero() => {
+                    LIQUIDITY_PROVIDERS.remove(storage, (market_id, holder.clone()));
+                    shares_value(market.shares_yes, market.shares_no, rate_yes, rate_no)
+                        .multiply_ratio(provider.contributed_liquidity, market.total_liquidity)
+                }
+                _ => Uint128::zero(),
+            };
+
+        // A bond the creator never put behind a resolution goes back with the
+        // claim, so does a spam deposit the market earned back
+        let bond_refund = if *holder == market.creator {
+            std::mem::take(&mut market.creator_bond) + std::mem::take(&mut market.spam_deposit)
+        } else {
+            Uint128::zero()
+        };
+        market.collateral = market
+            .collateral
+            .saturating_sub(shares_payout + pool_payout);
+        MARKETS.save(storage, market_id, market)?;
+
+        Ok((shares_payout, pool_payout, bond_refund))
+    }
+
+    fn validate_resolver(
+        deps: Deps,
+        resolver: Option<ResolverMsg>,
+        close_time: Timestamp,
+        resolution_time: Timestamp,
+    ) -> Result<Resolver, ContractError> {
+        let api = deps.api;
+        match resolver.unwrap_or(ResolverMsg::Creator {}) {
+            ResolverMsg::Creator {} => Ok(Resolver::Creator),
+            ResolverMsg::Committee {
+                members,
+                threshold,
+                timeout,
+            } => {
+                let members = members
+                    .iter()
+                    .map(|member| api.addr_validate(member))
+                    .collect::<StdResult<Vec<_>>>()?;
+                let mut unique = members.clone();
+                unique.sort();
+                unique.dedup();
+                if unique.len() != members.len()
+                    || threshold == 0
+                    || threshold as usize > members.len()
+                {
+                    return Err(ContractError::InvalidCommittee {});
+                }
+                Ok(Resolver::Committee {
+                    members,
+                    threshold,
+                    timeout,
+                })
+            }
+            ResolverMsg::Oracle {
+                sources,
+                tolerance_bps,
+            } => {
+                if sources.is_empty() || tolerance_bps > 10_000 {
+                    return Err(ContractError::InvalidOracleSources {});
+                }
+                let sources = sources
+                    .into_iter()
+                    .map(|source| {
+                        Ok(OracleSource {
+                            contract: api.addr_validate(&source.contract)?,
+                            query_key: source.query_key,
+                        })
+                    })
+                    .collect::<StdResult<_>>()?;
+                Ok(Resolver::Oracle {
+                    sources,
+                    tolerance_bps,
+                })
+            }
+            ResolverMsg::PriceFeed {
+                asset,
+                comparator,
+                threshold,
+                observation_time,
+                tolerance_bps,
+            } => {
+                if CONFIG.load(deps.storage)?.price_feeds.is_empty() {
+                    return Err(ContractError::PriceFeedNotConfigured {});
+                }
+                if observation_time < close_time || observation_time > resolution_time {
+                    return Err(ContractError::InvalidObservationTime {});
+                }
+                Ok(Resolver::PriceFeed {
+                    asset,
+                    comparator,
+                    threshold,
+                    observation_time,
+                    tolerance_bps,
+                })
+            }
+        }
+    }
+
+    /// Majority answer of the oracle sources, every one of which must have
+    /// answered. Also tells whether the dissent stays within the tolerance.
+    fn aggregate_answers(
+        deps: Deps,
+        env: &Env,
+        sources: &[OracleSource],
+        tolerance_bps: u64,
+    ) -> Result<(String, Vec<OracleReport>, bool), ContractError> {
+        let mut reports = vec![];
+        let mut tally: Vec<(String, u64)> = vec![];
+        for source in sources {
+            let response: AnswerResponse = deps.querier.query_wasm_smart(
+                &source.contract,
+                &OracleQueryMsg::Answer {
+                    key: source.query_key.clone(),
+                },
+            )?;
+            let answer = response
+                .answer
+                .ok_or(ContractError::OracleNotAnswered {
+                    key: source.query_key.clone(),
+                })?
+                .to_uppercase();
+            match tally.iter_mut().find(|(outcome, _)| *outcome == answer) {
+                Some((_, count)) => *count += 1,
+                None => tally.push((answer.clone(), 1)),
+            }
+            reports.push(OracleReport {
+                source: source.contract.clone(),
+                query_key: source.query_key.clone(),
+                answer,
+                reported_at: env.block.time,
+            });
+        }
+
+        // Ties go to the answer reported first
+        let (outcome, count) = tally
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, count)| *count)
+            .unwrap_or_default();
+        let total = sources.len() as u64;
+        let agreed = (total - count) * 10_000 <= total * tolerance_bps;
+        Ok((outcome, reports, agreed))
+    }
+
+    /// Price of `asset` on each configured feed, checked for staleness and
+    /// confidence
+    fn observe_prices(
+        deps: Deps,
+        env: &Env,
+        asset: &str,
+        observation_time: Timestamp,
+    ) -> Result<Vec<OracleReport>, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        if config.price_feeds.is_empty() {
+            return Err(ContractError::PriceFeedNotConfigured {});
+        }
+
+        let mut reports = vec![];
+        for feed in &config.price_feeds {
+            let response: PriceFeedResponse = deps.querier.query_wasm_smart(
+                feed,
+                &PriceFeedQueryMsg::PriceFeed {
+                    id: asset.to_string(),
+                },
+            )?;
+            let price = observed_price(
+                &response.price_feed.price,
+                observation_time,
+                config.max_price_age,
+                config.max_confidence_bps,
+            )?;
+            reports.push(OracleReport {
+                source: feed.clone(),
+                query_key: asset.to_string(),
+                answer: price.to_string(),
+                reported_at: env.block.time,
+            });
+        }
+        Ok(reports)
+    }
+
+    /// Median of the prices reported by the feeds. Also tells whether the
+    /// spread between feeds stays within the tolerance.
+    fn median_price(reports: &[OracleReport], tolerance_bps: u64) -> StdResult<(Decimal, bool)> {
+        let mut prices = reports
+            .iter()
+            .map(|report| Decimal::from_str(&report.answer))
+            .collect::<StdResult<Vec<_>>>()?;
+        prices.sort();
+        let middle = prices.len() / 2;
+        let median = if prices.len() % 2 == 0 {
+            (prices[middle - 1] + prices[middle]) / Uint128::new(2)
+        } else {
+            prices[middle]
+        };
+        let spread = prices[prices.len() - 1] - prices[0];
+        let agreed = spread <= median * Decimal::from_ratio(tolerance_bps, 10_000u128);
+        Ok((median, agreed))
+    }
+
+    /// Converts a feed price to a decimal after checking it was published in
+    /// the `max_age` seconds following the observation time and that its
+    /// confidence interval is narrow enough
+    fn observed_price(
+        price: &Price,
+        observation_time: Timestamp,
+        max_age: u64,
+        max_confidence_bps: u64,
+    ) -> Result<Decimal, ContractError> {
+        let observed = observation_time.seconds() as i64;
+        if price.publish_time < observed || price.publish_time > observed + max_age as i64 {
+            return Err(ContractError::StalePrice {
+                publish_time: price.publish_time,
+            });
+        }
+        if price.price <= 0 {
+            return Err(ContractError::InvalidPrice { price: price.price });
+        }
+        if price.conf as u128 * 10_000 > price.price as u128 * max_confidence_bps as u128 {
+            return Err(ContractError::PriceTooUncertain {
+                price: price.price,
+                conf: price.conf,
+            });
+        }
+
+        let invalid = |_| ContractError::InvalidPrice { price: price.price };
+        if price.expo <= 0 {
+            Decimal::from_atomics(price.price as u128, price.expo.unsigned_abs()).map_err(invalid)
+        } else {
+            let scale = 10u128
+                .checked_pow(price.expo as u32)
+                .ok_or(ContractError::InvalidPrice { price: price.price })?;
+            Decimal::from_atomics(price.price as u128 * scale, 0).map_err(invalid)
+        }
+    }
+
+    /// End of the voting period of a committee resolved market
+    fn committee_deadline(market: &Market) -> Option<Timestamp> {
+        match market.resolver {
+            Resolver::Committee { timeout, .. } => {
+                Some(market.resolution_time.plus_seconds(timeout))
+            }
+            _ => None,
+        }
+    }
+
+    /// Opens the dispute window on `outcome`, backed by `stake` as round 0
+    fn open_proposal(
+        storage: &mut dyn Storage,
+        env: &Env,
+        market_id: u64,
+        market: &mut Market,
+        proposer: Addr,
+        outcome: String,
+        stake: Uint128,
+    ) -> StdResult<Proposal> {
+        let config = CONFIG.load(storage)?;
+        let proposal = Proposal {
+            proposer: proposer.clone(),
+            outcome: outcome.clone(),
+            bond: stake,
+            expires_at: env.block.time.plus_seconds(config.liveness),
+            round: 0,
+        };
+        PROPOSALS.save(storage, market_id, &proposal)?;
+        FINALIZE_QUEUE.save(
+            storage,
+            (proposal.expires_at.seconds(), market_id),
+            &Empty {},
+        )?;
+        let round = DisputeRound {
+            staker: proposer,
+            outcome,
+            stake,
+            staked_at: env.block.time,
+        };
+        DISPUTE_ROUNDS.save(storage, (market_id, 0), &round)?;
+
+        market.status = MarketStatus::Proposed;
+        MARKETS.save(storage, market_id, market)?;
+        Ok(proposal)
+    }
+
+    /// Settles the outcome of a market that is ready to be finalized, returning
+    /// the outcome, the stake payouts and the settlement attributes
+    fn finalize(
+        storage: &mut dyn Storage,
+        env: &Env,
+        market_id: u64,
+        actor: Addr,
+    ) -> Result<(String, Vec<BankMsg>, Vec<Attribute>), ContractError> {
+        let mut market = MARKETS.load(storage, market_id)?;
+
+        let status = market.status_at(env.block.time);
+        let (outcome, payouts) = match status {
+            MarketStatus::Resolved => (market.winning_outcome.clone().unwrap_or_default(), vec![]),
+            MarketStatus::Proposed | MarketStatus::Disputed => {
+                let proposal = PROPOSALS.load(storage, market_id)?;
+                let config = CONFIG.load(storage)?;
+                // Disputes that used up their rounds wait for the arbiter
+                if status == MarketStatus::Disputed && proposal.round >= config.max_dispute_rounds {
+                    return Err(ContractError::InvalidTransition {
+                        from: status,
+                        to: MarketStatus::Finalized,
+                    });
+                }
+                if env.block.time < proposal.expires_at {
+                    return Err(ContractError::LivenessNotExpired {
+                        expires_at: proposal.expires_at,
+                    });
+                }
+                let payouts = settle_stakes(storage, market_id, &market, &proposal.outcome)?;
+                (proposal.outcome, payouts)
+            }
+            // A committee that missed its deadline leaves the market unanswerable
+            MarketStatus::Closed
+                if committee_deadline(&market)
+                    .is_some_and(|deadline| env.block.time >= deadline) =>
+            {
+                ("INVALID".to_string(), vec![])
+            }
+            _ => {
+                return Err(ContractError::InvalidTransition {
+                    from: status,
+                    to: MarketStatus::Finalized,
+                })
+            }
+        };
+
+        let attributes = settle_market(storage, market_id, &mut market, outcome.clone())?;
+        log_resolution(
+            storage,
+            env,
+            market_id,
+            ResolutionAction::Finalized,
+            actor,
+            Some(outcome.clone()),
+            vec![],
+        )?;
+
+        Ok((outcome, payouts, attributes))
+    }
+
+    /// Appends a step to the resolution history of a market
+    fn log_resolution(
+        storage: &mut dyn Storage,
+        env: &Env,
+        market_id: u64,
+        action: ResolutionAction,
+        actor: Addr,
+        outcome: Option<String>,
+        evidence: Vec<String>,
+    ) -> StdResult<()> {
+        let index = RESOLUTION_LOG
+            .prefix(market_id)
+            .keys(storage, None, None, Order::Descending)
+            .next()
+            .transpose()?
+            .map_or(0, |last| last + 1);
+        let step = ResolutionStep {
+            action,
+            actor,
+            outcome,
+            evidence,
+            height: env.block.height,
+            time: env.block.time,
+        };
+        RESOLUTION_LOG.save(storage, (market_id, index), &step)
+    }
+
+    /// Payouts of the stakes behind a proposal once `outcome` is final: stakers
+    /// of that outcome get their stake back plus a pro-rata share of the losing
+    /// stakes. If nobody staked on it every stake is refunded. A creator whose
+    /// own resolution is overturned is slashed, part of the bond going to the
+    /// treasury, and the outcome is recorded in the creator's reputation.
+    fn settle_stakes(
+        storage: &mut dyn Storage,
+        market_id: u64,
+        market: &Market,
+        outcome: &str,
+    ) -> StdResult<Vec<BankMsg>> {
+        let config = CONFIG.load(storage)?;
+        let denom = &market.collateral_denom;
+        let rounds = DISPUTE_ROUNDS
+            .prefix(market_id)
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, round)| round))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let creator_round = rounds
+            .first()
+            .filter(|round| round.staker == market.creator)
+            .cloned();
+        let slashed = match &creator_round {
+            Some(round) if round.outcome != outcome => round.stake,
+            _ => Uint128::zero(),
+        };
+        let mut to_treasury = slashed.multiply_ratio(config.slash_treasury_bps, 10_000u128);
+
+        let (winning, losing) = rounds.iter().fold(
+            (Uint128::zero(), Uint128::zero()),
+            |(winning, losing), round| {
+                if round.outcome == outcome {
+                    (winning + round.stake, losing)
+                } else {
+                    (winning, losing + round.stake)
+                }
+            },
+        );
+
+        let losing = losing - to_treasury;
+        if winning.is_zero() {
+            to_treasury = slashed;
+        }
+
+        let mut payouts: Vec<(Addr, Uint128)> = vec![];
+        for (index, round) in rounds.into_iter().enumerate() {
+            let amount = if winning.is_zero() {
+                // Nobody backed the final outcome, stakes other than a slashed
+                // bond go back
+                if index == 0 && !slashed.is_zero() {
+                    continue;
+                }
+                round.stake
+            } else if round.outcome == outcome {
+                round.stake + losing.multiply_ratio(round.stake, winning)
+            } else {
+                continue;
+            };
+            match payouts
+                .iter_mut()
+                .find(|(staker, _)| *staker == round.staker)
+            {
+                Some((_, total)) => *total += amount,
+                None => payouts.push((round.staker, amount)),
+            }
+        }
+
+        if !to_treasury.is_zero() {
+            payouts.push((config.treasury, to_treasury));
+        }
+
+        if let Some(round) = creator_round {
+            let mut stats = CREATOR_STATS
+                .may_load(storage, &market.creator)?
+                .unwrap_or_default();
+            if round.outcome == outcome {
+                stats.resolutions_upheld += 1;
+            } else {
+                stats.resolutions_overturned += 1;
+                stats.total_slashed += slashed;
+            }
+            CREATOR_STATS.save(storage, &market.creator, &stats)?;
+        }
+
+        Ok(payouts
+            .into_iter()
+            .filter(|(_, amount)| !amount.is_zero())
+            .map(|(recipient, amount)| BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![Coin::new(amount, denom)],
+            })
+            .collect())
+    }
+
+    /// Stores a validated outcome. Invalid outcomes void the market right away,
+    /// others wait for FinalizeMarket.
+    fn record_outcome(
+        storage: &mut dyn Storage,
+        env: &Env,
+        market_id: u64,
+        market: &mut Market,
+        outcome: String,
+    ) -> StdResult<Vec<Attribute>> {
+        if outcome == "INVALID" {
+            return settle_market(storage, market_id, market, outcome);
+        }
+        market.status = MarketStatus::Resolved;
+        market.winning_outcome = Some(outcome);
+        MARKETS.save(storage, market_id, market)?;
+        FINALIZE_QUEUE.save(storage, (env.block.time.seconds(), market_id), &Empty {})?;
+        Ok(vec![])
+    }
+
+    /// A market can only be resolved once trading has closed and its resolution
+    /// time has been reached
+    fn ensure_resolvable(
+        market: &Market,
+        env: &Env,
+        to: MarketStatus,
+    ) -> Result<(), ContractError> {
+        let status = market.status_at(env.block.time);
+        if status != MarketStatus::Closed {
+            return Err(ContractError::InvalidTransition { from: status, to });
+        }
+
+        if env.block.time < market.resolution_time {
+            return Err(ContractError::ResolutionTooEarly {
+                resolution_time: market.resolution_time,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks shared by every way of reporting an outcome. Returns the status the
+    /// market ends in once the outcome is final.
+    fn validate_outcome(
+        storage: &dyn Storage,
+        market_id: u64,
+        market: &Market,
+        outcome: &str,
+    ) -> Result<MarketStatus, ContractError> {
+        // Conditional markets wait for their parent to settle the right way
+        if let Some(condition) = &market.condition {
+            let parent = MARKETS.load(storage, condition.market_id)?;
+            if parent.status != MarketStatus::Finalized
+                || parent.winning_outcome.as_ref() != Some(&condition.outcome)
+            {
+                return Err(ContractError::ConditionNotMet {
+                    market_id: condition.market_id,
+                    outcome: condition.outcome.clone(),
+                });
+            }
+        }
+
+        // An invalid outcome voids the market, anything else must name a side
+        let status = match outcome {
+            "YES" | "NO" => MarketStatus::Finalized,
+            "INVALID" => MarketStatus::Voided,
+            _ => {
+                return Err(ContractError::InvalidOutcome {
+                    outcome: outcome.to_string(),
+                })
+            }
+        };
+
+        // At most one market of an event group can resolve YES
+        if let (Some(group_id), "YES") = (market.group_id, outcome) {
+            let group = EVENT_GROUPS.load(storage, group_id)?;
+            for other_id in group.market_ids.into_iter().filter(|id| *id != market_id) {
+                let other = MARKETS.load(storage, other_id)?;
+                // Outcomes still open to disputes count too
+                let outcome = match other.status {
+                    MarketStatus::Resolved | MarketStatus::Finalized => other.winning_outcome,
+                    MarketStatus::Proposed | MarketStatus::Disputed => PROPOSALS
+                        .may_load(storage, other_id)?
+                        .map(|proposal| proposal.outcome),
+                    _ => None,
+                };
+                if outcome.as_deref() == Some("YES") {
+                    return Err(ContractError::GroupWinnerExists {
+                        group_id,
+                        market_id: other_id,
+                    });
+                }
+            }
+        }
+
+        Ok(status)
+    }
+
+    /// Apply a final outcome: the market is finalized, or voided for "INVALID".
+    /// Conditional markets that can no longer be met are voided and, for a YES in
+    /// an event group, the other markets of the group settle as NO, save those
+    /// with a pending proposal which settle their stakes on their own.
+    fn settle_market(
+        storage: &mut dyn Storage,
+        market_id: u64,
+        market: &mut Market,
+        outcome: String,
+    ) -> StdResult<Vec<Attribute>> {
+        let mut reverted = match market.event_time {
+            Some(event_time) => revert_trades(storage, market_id, market, event_time)?,
+            None => 0,
+        };
+
+        market.status = if outcome == "INVALID" {
+            MarketStatus::Voided
+        } else {
+            MarketStatus::Finalized
+        };
+        market.winning_outcome = Some(outcome);
+        forfeit_spam_deposit(storage, market)?;
+        MARKETS.save(storage, market_id, market)?;
+
+        let mut voided = void_unmet_children(storage, market_id, Some(market))?;
+
+        let mut resolved = vec![];
+        if let (Some(group_id), Some("YES")) = (market.group_id, market.winning_outcome.as_deref())
+        {
+            let group = EVENT_GROUPS.load(storage, group_id)?;
+            for other_id in group.market_ids.into_iter().filter(|id| *id != market_id) {
+                let mut other = MARKETS.load(storage, other_id)?;
+                // A pending proposal can no longer turn YES, it finalizes or
+                // goes to the arbiter with its stakes
+                if matches!(
+                    other.status,
+                    MarketStatus::Voided
+                        | MarketStatus::Finalized
+                        | MarketStatus::Proposed
+                        | MarketStatus::Disputed
+                ) {
+                    continue;
+                }
+                // Trades made after the event settling the group are undone too
+                if let Some(event_time) = other.event_time.or(market.event_time) {
+                    reverted += revert_trades(storage, other_id, &mut other, event_time)?;
+                }
+                other.status = MarketStatus::Finalized;
+                other.winning_outcome = Some("NO".to_string());
+                forfeit_spam_deposit(storage, &mut other)?;
+                MARKETS.save(storage, other_id, &other)?;
+
+                resolved.push(other_id.to_string());
+                voided.extend(void_unmet_children(storage, other_id, Some(&other))?);
+            }
+        }
+
+        let mut attributes = voided_attribute(&voided);
+        if reverted > 0 {
+            attributes.push(Attribute::new("reverted_trades", reverted.to_string()));
+        }
+        if !resolved.is_empty() {
+            attributes.push(Attribute::new("resolved_no", resolved.join(",")));
+        }
+        Ok(attributes)
+    }
+
+    /// A market settling without the minimum activity, or voided, loses its spam
+    /// deposit to the keeper pool. Otherwise the deposit waits for the creator's claim.
+    fn forfeit_spam_deposit(storage: &mut dyn Storage, market: &mut Market) -> StdResult<()> {
+        let config = CONFIG.load(storage)?;
+        let earned =
+            market.status == MarketStatus::Finalized && market.volume >= config.min_activity;
+        if earned || market.shutdown {
+            return Ok(());
+        }
+        let deposit = std::mem::take(&mut market.spam_deposit);
+        if !deposit.is_zero() {
+            KEEPER_POOL.update(storage, &market.collateral_denom, |balance| {
+                StdResult::Ok(balance.unwrap_or_default() + deposit)
+            })?;
+        }
+        Ok(())
+    }
+
+    fn ensure_within_limits(
+        market_id: u64,
+        market: &Market,
+        buyer: &Buyer,
+    ) -> Result<(), ContractError> {
+        if let Some(limit) = market.max_position {
+            let position = limit.position(buyer);
+            if position > limit.amount() {
+                return Err(ContractError::PositionLimitExceeded {
+                    market_id,
+                    limit: limit.amount(),
+                    position,
+                });
+            }
+        }
+        match market.max_open_interest {
+            Some(limit) if market.open_interest > limit => {
+                Err(ContractError::OpenInterestLimitExceeded {
+                    market_id,
+                    limit,
+                    open_interest: market.open_interest,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn record_trade(storage: &mut dyn Storage, market_id: u64, trade: Trade) -> StdResult<()> {
+        let index = TRADES
+            .prefix(market_id)
+            .keys(storage, None, None, Order::Descending)
+            .next()
+            .transpose()?
+            .map_or(0, |last| last + 1);
+        TRADES.save(storage, (market_id, index), &trade)
+    }
+
+    /// Unwind the fills made from `event_time` on, latest first: their effect on
+    /// the pool is reversed and traders get their shares back or lose the ones
+    /// bought. What the trades moved in collateral is settled with the claim.
+    fn revert_trades(
+        storage: &mut dyn Storage,
+        market_id: u64,
+        market: &mut Market,
+        event_time: Timestamp,
+    ) -> StdResult<u32> {
+        let late_trades = TRADES
+            .prefix(market_id)
+            .range(storage, None, None, Order::Descending)
+            .take_while(|item| {
+                item.as_ref()
+                    .map_or(true, |(_, trade)| trade.executed_at >= event_time)
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut reverted = 0;
+        for (index, mut trade) in late_trades {
+            if trade.reverted {
+                continue;
+            }
+            market.shares_yes =
+                (market.shares_yes + trade.pool_before.0).saturating_sub(trade.pool_after.0);
+            market.shares_no =
+                (market.shares_no + trade.pool_before.1).saturating_sub(trade.pool_after.1);
+
+            let key = (market_id, trade.trader.clone());
+            let mut buyer = BUYERS.may_load(storage, key.clone())?.unwrap_or(Buyer {
+                address: trade.trader.clone(),
+                shares_yes: Uint128::zero(),
+                shares_no: Uint128::zero(),
+                reverted_cost: Uint128::zero(),
+                reverted_proceeds: Uint128::zero(),
+                spent: Uint128::zero(),
+            });
+            let shares = if trade.outcome == "YES" {
+                &mut buyer.shares_yes
+            } else {
+                &mut buyer.shares_no
+            };
+            match trade.side {
+                TradeSide::Buy => {
+                    *shares = shares.saturating_sub(trade.shares);
+                    buyer.reverted_cost += trade.collateral;
+                }
+                TradeSide::Sell => {
+                    *shares += trade.shares;
+                    buyer.reverted_proceeds += trade.collateral;
+                }
+            }
+            BUYERS.save(storage, key, &buyer)?;
+
+            trade.reverted = true;
+            TRADES.save(storage, (market_id, index), &trade)?;
+            reverted += 1;
+        }
+
+        if reverted > 0 {
+            market.price_yes = calculate_price(market.shares_yes, market.shares_no);
+            market.price_no = calculate_price(market.shares_no, market.shares_yes);
+            MARKETS.save(storage, market_id, market)?;
+        }
+        Ok(reverted)
+    }
+
+    /// The event time reported at resolution cannot lie ahead, before the
+    /// market was created or more than `MAX_EVENT_LEAD` before its close
+    fn validate_event_time(
+        env: &Env,
+        market: &Market,
+        event_time: Option<Timestamp>,
+    ) -> Result<Option<Timestamp>, ContractError> {
+        let earliest = market
+            .created_at
+            .max(market.close_time.minus_seconds(MAX_EVENT_LEAD));
+        match event_time {
+            Some(event_time) if event_time > env.block.time || event_time < earliest => {
+                Err(ContractError::InvalidEventTime {})
+            }
+            _ => Ok(event_time),
+        }
+    }
+
+    /// Void the conditional markets of `parent_id` whose condition can no longer be
+    /// met, cascading to their own conditional markets. `parent` is `None` when the
+    /// parent was deleted.
+    fn void_unmet_children(
+        storage: &mut dyn Storage,
+        parent_id: u64,
+        parent: Option<&Market>,
+    ) -> StdResult<Vec<u64>> {
+        let children = CHILD_MARKETS
+            .prefix(parent_id)
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut voided = vec![];
+        for child_id in children {
+            let mut child = MARKETS.load(storage, child_id)?;
+            if matches!(child.status, MarketStatus::Voided | MarketStatus::Finalized) {
+                continue;
+            }
+
+            let condition_met = match (parent, &child.condition) {
+                (Some(parent), Some(condition)) => {
+                    parent.status == MarketStatus::Finalized
+                        && parent.winning_outcome.as_ref() == Some(&condition.outcome)
+                }
+                _ => false,
+            };
+            if condition_met {
+                continue;
+            }
+
+            child.status = MarketStatus::Voided;
+            child.winning_outcome = Some("INVALID".to_string());
+            MARKETS.save(storage, child_id, &child)?;
+
+            voided.push(child_id);
+            voided.extend(void_unmet_children(storage, child_id, Some(&child))?);
+        }
+        Ok(voided)
+    }
+
+    fn voided_attribute(voided: &[u64]) -> Vec<Attribute> {
+        if voided.is_empty() {
+            return vec![];
+        }
+        let ids = voided
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        vec![Attribute::new("voided_markets", ids)]
+    }
+
+    /// Trading and liquidity changes are only allowed while the market is open
+    fn ensure_open(market: &Market, env: &Env, market_id: u64) -> Result<(), ContractError> {
+        let status = market.status_at(env.block.time);
+        if status != MarketStatus::Open {
+            return Err(ContractError::MarketNotOpen { market_id, status });
+        }
+        Ok(())
+    }
+
+    /// Buys, sells and liquidity adds stop while the market or the whole
+    /// contract is paused
+    fn ensure_not_paused(
+        storage: &dyn Storage,
+        market: &Market,
+        market_id: u64,
+    ) -> Result<(), ContractError> {
+        if CONFIG.load(storage)?.paused {
+            return Err(ContractError::Paused {});
+        }
+        if market.paused {
+            return Err(ContractError::MarketPaused { market_id });
+        }
+        Ok(())
+    }
+
+    /// Private markets only let in their creator and participants
+    fn ensure_participant(
+        storage: &dyn Storage,
+        market: &Market,
+        market_id: u64,
+        address: &Addr,
+    ) -> Result<(), ContractError> {
+        if market.visibility == Visibility::Private
+            && *address != market.creator
+            && !PARTICIPANTS.has(storage, (market_id, address))
+        {
+            return Err(ContractError::NotParticipant {
+                market_id,
+                address: address.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Ask the compliance contract, if any, whether `address` may take `action`
+    fn ensure_allowed(
+        deps: Deps,
+        address: &Addr,
+        action: ComplianceAction,
+        market_id: u64,
+    ) -> Result<(), ContractError> {
+        let Some(compliance) = CONFIG.load(deps.storage)?.compliance else {
+            return Ok(());
+        };
+        let response: IsAllowedResponse = deps.querier.query_wasm_smart(
+            compliance,
+            &ComplianceQueryMsg::IsAllowed {
+                address: address.to_string(),
+                action,
+                market_id,
+            },
+        )?;
+        if !response.allowed {
+            return Err(ContractError::NotAllowed {
+                address: address.clone(),
+                action,
+                market_id,
+            });
+        }
+        Ok(())
+    }
+
+    /// The sender must attach exactly `amount` of the market collateral
+    fn ensure_funds(info: &MessageInfo, denom: &str, amount: Uint128) -> Result<(), ContractError> {
+        if amount.is_zero() {
+            nonpayable(info)?;
+            return Ok(());
+        }
+        let received = must_pay(info, denom)?;
+        if received != amount {
+            return Err(ContractError::FundsMismatch {
+                expected: amount,
+                received,
+            });
+        }
+        Ok(())
+    }
+
+    /// Collateral paid per YES and NO share, scaled by `DECIMAL_PRECISION`.
+    /// Returns `None` while the market is not settled.
+    fn payout_rates(market: &Market) -> Option<(Uint128, Uint128)> {
+        let one = Uint128::from(DECIMAL_PRECISION);
+        match (market.status, market.winning_outcome.as_deref()) {
+            (MarketStatus::Finalized, Some("YES")) => Some((one, Uint128::zero())),
+            (MarketStatus::Finalized, Some("NO")) => Some((Uint128::zero(), one)),
+            (MarketStatus::Voided, _) => Some(match market.void_fallback {
+                VoidFallback::EqualSplit => {
+                    let half = one / Uint128::new(2);
+                    (half, half)
+                }
+                VoidFallback::LastTradedPrices => (market.price_yes, market.price_no),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Collateral `c` paid for selling `shares` of the outcome with pool `sold`
+    /// against pool `other`, the largest with `(sold + shares - c) * (other - c)`
+    /// still at least `sold * other`: the smaller root of
+    /// `c^2 - (sold + shares + other) * c + shares * other`, rounded in favor of
+    /// the pool
+    fn sell_payout(sold: Uint128, other: Uint128, shares: Uint128) -> StdResult<Uint128> {
+        let sum = sold.checked_add(shares)?.checked_add(other)?;
+        let discriminant = sum
+            .checked_mul(sum)?
+            .checked_sub(Uint128::new(4).checked_mul(shares)?.checked_mul(other)?)?;
+        let mut root = discriminant.isqrt();
+        if root * root < discriminant {
+            root += Uint128::one();
+        }
+        Ok((sum.checked_sub(root)?) / Uint128::new(2))
+    }
+
+    fn shares_value(
+        shares_yes: Uint128,
+        shares_no: Uint128,
+        rate_yes: Uint128,
+        rate_no: Uint128,
+    ) -> Uint128 {
+        let precision = Uint128::from(DECIMAL_PRECISION);
+        shares_yes.multiply_ratio(rate_yes, precision)
+            + shares_no.multiply_ratio(rate_no, precision)
+    }
+
+    fn calculate_price(share_pool: Uint128, other_pool: Uint128) -> Uint128 {
+        let total_shares = share_pool + other_pool;
+        if total_shares.is_zero() {
+            return Uint128::zero();
+        }
+        other_pool * Uint128::from(DECIMAL_PRECISION) / total_shares
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::PendingOwner {} => to_json_binary(&query::pending_owner(deps)?),
+        QueryMsg::HasRole { role, address } => {
+            to_json_binary(&query::has_role(deps, role, address)?)
+        }
+        QueryMsg::RoleMembers {
+            role,
+            start_after,
+            limit,
+        } => to_json_binary(&query::role_members(deps, role, start_after, limit)?),
+        QueryMsg::GetMarket { market_id } => to_json_binary(&query::market(deps, env, market_id)?),
+        QueryMsg::ListMarkets {
+            start_after,
+            limit,
+            include_private,
+        } => to_json_binary(&query::list_markets(
+            deps,
+            env,
+            start_after,
+            limit,
+            include_private.unwrap_or(false),
+        )?),
+        QueryMsg::Participants {
+            market_id,
+            start_after,
+            limit,
+        } => to_json_binary(&query::participants(deps, market_id, start_after, limit)?),
+        QueryMsg::GetEventGroup { group_id } => {
+            to_json_binary(&query::event_group(deps, env, group_id)?)
+        }
+        QueryMsg::GetProposal { market_id } => to_json_binary(&query::proposal(deps, market_id)?),
+        QueryMsg::CreatorReputation { creator } => {
+            to_json_binary(&query::creator_reputation(deps, creator)?)
+        }
+        QueryMsg::GetOracleReports { market_id } => {
+            to_json_binary(&query::oracle_reports(deps, market_id)?)
+        }
+        QueryMsg::GetDisputeRounds { market_id } => {
+            to_json_binary(&query::dispute_rounds(deps, market_id)?)
+        }
+        QueryMsg::GetVotes { market_id } => to_json_binary(&query::votes(deps, market_id)?),
+        QueryMsg::KeeperPool { denom } => to_json_binary(&query::keeper_pool(deps, denom)?),
+        QueryMsg::ResolutionHistory { market_id } => {
+            to_json_binary(&query::resolution_history(deps, market_id)?)
+        }
+        QueryMsg::Headroom { market_id, address } => {
+            to_json_binary(&query::headroom(deps, market_id, address)?)
+        }
+    }
+}
+
+pub mod query {
+    use super::*;
+
+    pub fn market(deps: Deps, env: Env, market_id: u64) -> StdResult<MarketResponse> {
+        let market = MARKETS.load(deps.storage, market_id)?;
+        let paused = CONFIG.load(deps.storage)?.paused;
+        Ok(market_response(&env, market_id, market, paused))
+    }
+
+    pub fn list_markets(
+        deps: Deps,
+        env: Env,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        include_private: bool,
+    ) -> StdResult<MarketsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+        let paused = CONFIG.load(deps.storage)?.paused;
+
+        let markets = MARKETS
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|item| {
+                include_private
+                    || item
+                        .as_ref()
+                        .map_or(true, |(_, market)| market.visibility == Visibility::Public)
+            })
+            .take(limit)
+            .map(|item| {
+                item.map(|(market_id, market)| market_response(&env, market_id, market, paused))
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(MarketsResponse { markets })
+    }
+
+    pub fn proposal(deps: Deps, market_id: u64) -> StdResult<ProposalResponse> {
+        let proposal = PROPOSALS.load(deps.storage, market_id)?;
+        Ok(ProposalResponse {
+            proposer: proposal.proposer,
+            outcome: proposal.outcome,
+            bond: proposal.bond,
+            expires_at: proposal.expires_at,
+            round: proposal.round,
+        })
+    }
+
+    pub fn creator_reputation(deps: Deps, creator: String) -> StdResult<CreatorReputationResponse> {
+        let creator = deps.api.addr_validate(&creator)?;
+        let stats = CREATOR_STATS
+            .may_load(deps.storage, &creator)?
+            .unwrap_or_default();
+        Ok(CreatorReputationResponse {
+            creator,
+            markets_created: stats.markets_created,
+            resolutions_upheld: stats.resolutions_upheld,
+            resolutions_overturned: stats.resolutions_overturned,
+            total_slashed: stats.total_slashed,
+        })
+    }
+
+    pub fn oracle_reports(deps: Deps, market_id: u64) -> StdResult<OracleReportsResponse> {
+        let reports = ORACLE_REPORTS
+            .prefix(market_id)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, report)| report))
+            .collect::<StdResult<_>>()?;
+        Ok(OracleReportsResponse { reports })
+    }
+
+    pub fn has_role(deps: Deps, role: Role, address: String) -> StdResult<HasRoleResponse> {
+        let address = deps.api.addr_validate(&address)?;
+        Ok(HasRoleResponse {
+            has_role: ROLES.has(deps.storage, (role.as_str(), &address)),
+        })
+    }
+
+    pub fn role_members(
+        deps: Deps,
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<RoleMembersResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?;
+        let start = start_after.as_ref().map(Bound::exclusive);
+
+        let members = ROLES
+            .prefix(role.as_str())
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        Ok(RoleMembersResponse { role, members })
+    }
+
+    pub fn participants(
+        deps: Deps,
+        market_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ParticipantsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?;
+        let start = start_after.as_ref().map(Bound::exclusive);
+
+        let participants = PARTICIPANTS
+            .prefix(market_id)
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        Ok(ParticipantsResponse {
+            market_id,
+            participants,
+        })
+    }
+
+    pub fn pending_owner(deps: Deps) -> StdResult<PendingOwnerResponse> {
+        let pending = PENDING_OWNER.may_load(deps.storage)?;
+        Ok(PendingOwnerResponse {
+            expiry: pending.as_ref().and_then(|pending| pending.expiry),
+            pending_owner: pending.map(|pending| pending.owner),
+        })
+    }
+
+    pub fn keeper_pool(deps: Deps, denom: String) -> StdResult<KeeperPoolResponse> {
+        let balance = KEEPER_POOL
+            .may_load(deps.storage, &denom)?
+            .unwrap_or_default();
+        let reward = CONFIG.load(deps.storage)?.keeper_reward;
+        Ok(KeeperPoolResponse {
+            denom,
+            balance,
+            reward,
+        })
+    }
+
+    pub fn headroom(deps: Deps, market_id: u64, address: String) -> StdResult<HeadroomResponse> {
+        let market = MARKETS.load(deps.storage, market_id)?;
+        let address = deps.api.addr_validate(&address)?;
+        let position_headroom = match market.max_position {
+            Some(limit) => {
+                let position = match BUYERS.may_load(deps.storage, (market_id, address.clone()))? {
+                    Some(buyer) => limit.position(&buyer),
+                    None => Uint128::zero(),
+                };
+                Some(limit.amount().saturating_sub(position))
+            }
+            None => None,
+        };
+        Ok(HeadroomResponse {
+            market_id,
+            address,
+            max_position: market.max_position,
+            position_headroom,
+            open_interest: market.open_interest,
+            open_interest_headroom: market
+                .max_open_interest
+                .map(|limit| limit.saturating_sub(market.open_interest)),
+        })
+    }
+
+    pub fn resolution_history(deps: Deps, market_id: u64) -> StdResult<ResolutionHistoryResponse> {
+        let steps = RESOLUTION_LOG
+            .prefix(market_id)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, step)| step))
+            .collect::<StdResult<_>>()?;
+        Ok(ResolutionHistoryResponse { market_id, steps })
+    }
+
+    pub fn dispute_rounds(deps: Deps, market_id: u64) -> StdResult<DisputeRoundsResponse> {
+        let proposal = PROPOSALS.load(deps.storage, market_id)?;
+        let config = CONFIG.load(deps.storage)?;
+
+        let rounds = DISPUTE_ROUNDS
+            .prefix(market_id)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                item.map(|(round, stake)| DisputeRoundInfo {
+                    round,
+                    staker: stake.staker,
+                    outcome: stake.outcome,
+                    stake: stake.stake,
+                    staked_at: stake.staked_at,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let next_stake = (proposal.round < config.max_dispute_rounds)
+            .then(|| {
+                rounds
+                    .last()
+                    .map(|last| (last.stake + last.stake).max(config.proposal_bond))
+            })
+            .flatten();
+
+        Ok(DisputeRoundsResponse {
+            rounds,
+            tentative_outcome: proposal.outcome,
+            expires_at: proposal.expires_at,
+            next_stake,
+            max_rounds: config.max_dispute_rounds,
+        })
+    }
+
+    pub fn votes(deps: Deps, market_id: u64) -> StdResult<VotesResponse> {
+        let market = MARKETS.load(deps.storage, market_id)?;
+        let (threshold, timeout) = match market.resolver {
+            Resolver::Committee {
+                threshold, timeout, ..
+            } => (threshold, timeout),
+            _ => return Err(StdError::generic_err("Market has no resolver committee")),
+        };
+
+        let votes = VOTES
+            .prefix(market_id)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(voter, outcome)| Vote { voter, outcome }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut tally: Vec<OutcomeTally> = vec![];
+        for vote in &votes {
+            match tally.iter_mut().find(|entry| entry.outcome == vote.outcome) {
+                Some(entry) => entry.votes += 1,
+                None => tally.push(OutcomeTally {
+                    outcome: vote.outcome.clone(),
+                    votes: 1,
+                }),
+            }
+        }
+
+        Ok(VotesResponse {
+            votes,
+            tally,
+            threshold,
+            deadline: market.resolution_time.plus_seconds(timeout),
+        })
+    }
+
+    pub fn event_group(deps: Deps, env: Env, group_id: u64) -> StdResult<EventGroupResponse> {
+        let group = EVENT_GROUPS.load(deps.storage, group_id)?;
+
+        let markets = group
+            .market_ids
+            .iter()
+            .map(|market_id| {
+                let market = MARKETS.load(deps.storage, *market_id)?;
+                Ok(GroupMarketPrice {
+                    market_id: *market_id,
+                    status: market.status_at(env.block.time),
+                    price_yes: market.price_yes,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let price_sum: Uint128 = markets.iter().map(|market| market.price_yes).sum();
+        let one = Uint128::from(DECIMAL_PRECISION);
+        let sums_to_one = price_sum.abs_diff(one) <= Uint128::from(PRICE_SUM_TOLERANCE);
+
+        Ok(EventGroupResponse {
+            group_id,
+            creator: group.creator,
+            description: group.description,
+            markets,
+            price_sum,
+            sums_to_one,
+        })
+    }
+
+    fn market_response(env: &Env, market_id: u64, market: Market, paused: bool) -> MarketResponse {
+        let now = env.block.time;
+        MarketResponse {
+            market_id,
+            status: market.status_at(now),
+            seconds_to_close: market.close_time.seconds().saturating_sub(now.seconds()),
+            seconds_to_resolution: market
+                .resolution_time
+                .seconds()
+                .saturating_sub(now.seconds()),
+            creator: market.creator,
+            description: market.description,
+            collateral_denom: market.collateral_denom,
+            created_at: market.created_at,
+            close_time: market.close_time,
+            resolution_time: market.resolution_time,
+            shares_yes: market.shares_yes,
+            shares_no: market.shares_no,
+            total_liquidity: market.total_liquidity,
+            price_yes: market.price_yes,
+            price_no: market.price_no,
+            winning_outcome: market.winning_outcome,
+            void_fallback: market.void_fallback,
+            condition: market.condition,
+            group_id: market.group_id,
+            resolver: market.resolver,
+            creator_bond: market.creator_bond,
+            spam_deposit: market.spam_deposit,
+            volume: market.volume,
+            max_position: market.max_position,
+            max_open_interest: market.max_open_interest,
+            open_interest: market.open_interest,
+            collateral: market.collateral,
+            event_time: market.event_time,
+            paused: paused || market.paused,
+            shutdown: market.shutdown,
+            visibility: market.visibility,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{
+        message_info, mock_dependencies, mock_dependencies_with_balance, mock_env,
+    };
+    use cosmwasm_std::{coins, from_json, Addr, CosmosMsg, SubMsg};
+
+    use crate::migrations::{LegacyMarket, LEGACY_MARKETS};
+    use crate::state::MarketCondition;
+
+    const CLOSE_IN: u64 = 3600;
+    const RESOLVE_IN: u64 = 7200;
+    const LIVENESS: u64 = 3600;
+    const BOND: u128 = 100;
+
+    fn instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            arbiter: None,
+            proposal_bond: Uint128::new(BOND),
+            liveness: LIVENESS,
+            price_feeds: vec![],
+            max_price_age: 0,
+            max_confidence_bps: 0,
+            max_dispute_rounds: 1,
+            creator_bond: Uint128::zero(),
+            treasury: None,
+            slash_treasury_bps: 0,
+            keeper_reward: Uint128::zero(),
+            default_collateral: "USDC".to_string(),
+            trading_fee_bps: 0,
+            min_liquidity: Uint128::one(),
+            max_liquidity: None,
+            collateral_min_liquidity: vec![],
+            creation_fee: vec![],
+            spam_deposit: vec![],
+            min_activity: Uint128::zero(),
+            open_market_creation: true,
+            compliance: None,
+        }
+    }
+
+    // Default market: 1000 USDC of liquidity, closing in an hour
+    fn market_msg() -> CreateMarketMsg {
+        let now = mock_env().block.time;
+        CreateMarketMsg {
+            description: "Will it rain tomorrow?".to_string(),
+            initial_liquidity: Uint128::new(1000),
+            close_time: now.plus_seconds(CLOSE_IN),
+            resolution_time: now.plus_seconds(RESOLVE_IN),
+            void_fallback: None,
+            condition: None,
+            resolver: None,
+            max_position: None,
+            max_open_interest: None,
+            visibility: None,
+        }
+    }
+
+    fn conditional_msg(parent_id: u64, outcome: &str) -> CreateMarketMsg {
+        CreateMarketMsg {
+            description: "Will policy X pass?".to_string(),
+            condition: Some(MarketCondition {
+                market_id: parent_id,
+                outcome: outcome.to_string(),
+            }),
+            ..market_msg()
+        }
+    }
+
+    // Creates a market as "creator", who holds the Resolver role, and returns its id
+    fn create_market(deps: DepsMut, msg: CreateMarketMsg) -> u64 {
+        let creator = Addr::unchecked("creator");
+        ROLES
+            .save(deps.storage, (Role::Resolver.as_str(), &creator), &Empty {})
+            .unwrap();
+        let info = message_info(&creator, &coins(msg.initial_liquidity.u128(), "USDC"));
+        let res = execute(deps, mock_env(), info, ExecuteMsg::CreateMarket(msg)).unwrap();
+        let market_id = res
+            .attributes
+            .iter()
+            .find(|attr| attr.key == "market_id")
+            .unwrap();
+        market_id.value.parse().unwrap()
+    }
+
+    // Helper function to initialize a market
+    fn setup_market(mut deps: DepsMut) -> u64 {
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        instantiate(deps.branch(), mock_env(), info, instantiate_msg()).unwrap();
+
+        create_market(deps, market_msg())
+    }
+
+    // Buys 300 USDC of YES, leaving the trader with 531 YES shares
+    // and the pool with 769 YES / 1300 NO
+    fn buy_yes(deps: DepsMut, market_id: u64) {
+        buy(deps, market_id, "YES");
+    }
+
+    // Buys 300 USDC of `outcome` as "trader"
+    fn buy(deps: DepsMut, market_id: u64, outcome: &str) {
+        let msg = ExecuteMsg::BuyShares {
+            market_id,
+            outcome: outcome.to_string(),
+            amount: Uint128::new(300),
+        };
+        let info = message_info(&Addr::unchecked("trader"), &coins(300, "USDC"));
+        execute(deps, mock_env(), info, msg).unwrap();
+    }
+
+    // Creates three markets and groups them as one event
+    fn setup_event_group(mut deps: DepsMut) -> (u64, Vec<u64>) {
+        let market_ids = vec![
+            setup_market(deps.branch()),
+            create_market(deps.branch(), market_msg()),
+            create_market(deps.branch(), market_msg()),
+        ];
+        let msg = ExecuteMsg::CreateEventGroup {
+            description: "Who wins the election?".to_string(),
+            market_ids: market_ids.clone(),
+        };
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        execute(deps, mock_env(), info, msg).unwrap();
+        (1, market_ids)
+    }
+
+    fn resolve(deps: DepsMut, market_id: u64, winning_outcome: &str) {
+        let msg = ExecuteMsg::ResolveMarket {
+            market_id,
+            winning_outcome: winning_outcome.to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        execute(deps, env_after(RESOLVE_IN), info, msg).unwrap();
+    }
+
+    // Finalizes a market once the dispute window of its resolution has passed
+    fn finalize(deps: DepsMut, market_id: u64) -> Response {
+        let info = message_info(&Addr::unchecked("anyone"), &[]);
+        let msg = ExecuteMsg::FinalizeMarket { market_id };
+        execute(deps, env_after(RESOLVE_IN + LIVENESS), info, msg).unwrap()
+    }
+
+    // Claims for `sender` and returns the collateral paid out
+    fn claim_payout(deps: DepsMut, market_id: u64, sender: &str) -> Uint128 {
+        let info = message_info(&Addr::unchecked(sender), &[]);
+        let res = execute(
+            deps,
+            env_after(RESOLVE_IN),
+            info,
+            ExecuteMsg::Claim { market_id },
+        )
+        .unwrap();
+        match &res.messages[0].msg {
+            cosmwasm_std::CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                assert_eq!(to_address, sender);
+                assert_eq!(amount[0].denom, "USDC");
+                amount[0].amount
+            }
+            msg => panic!("unexpected message: {:?}", msg),
+        }
+    }
+
+    // Environment `seconds` after the default mock block time
+    fn env_after(seconds: u64) -> Env {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        env
+    }
+
+    #[test]
+    fn test_update_config_is_owner_only() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            trading_fee_bps: Some(100),
+            min_liquidity: Some(Uint128::new(500)),
+            ..UpdateConfigMsg::default()
+        });
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        let bad_fee = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            trading_fee_bps: Some(10_001),
+            ..UpdateConfigMsg::default()
+        });
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), bad_fee).unwrap_err();
+        assert_eq!(err, ContractError::InvalidBps { bps: 10_001 });
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let config: Config = from_json(res).unwrap();
+        assert_eq!(config.owner, Some(Addr::unchecked("owner")));
+        assert_eq!(config.trading_fee_bps, 100);
+        assert_eq!(config.min_liquidity, Uint128::new(500));
+
+        let msg = CreateMarketMsg {
+            initial_liquidity: Uint128::new(100),
+            ..market_msg()
+        };
+        let info = message_info(&Addr::unchecked("creator"), &coins(100, "USDC"));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::CreateMarket(msg),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::LiquidityOutOfRange {
+                min: Uint128::new(500),
+                max: Uint128::MAX
+            }
+        );
+
+        // 1% of the purchase goes to the treasury
+        let msg = ExecuteMsg::BuyShares {
+            market_id,
+            outcome: "YES".to_string(),
+            amount: Uint128::new(300),
+        };
+        let info = message_info(&Addr::unchecked("trader"), &coins(300, "USDC"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "owner".to_string(),
+                amount: coins(3, "USDC"),
+            })]
+        );
+    }
+
+    #[test]
+    fn test_creation_fee_and_spam_deposit() {
+        let mut deps = mock_dependencies();
+        let treasury = deps.api.addr_make("treasury");
+        let msg = InstantiateMsg {
+            treasury: Some(treasury.to_string()),
+            collateral_min_liquidity: coins(5000, "ATOM"),
+            creation_fee: coins(10, "USDC"),
+            spam_deposit: coins(40, "USDC"),
+            min_activity: Uint128::new(200),
+            ..instantiate_msg()
+        };
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let create = |liquidity: u128| {
+            ExecuteMsg::CreateMarket(CreateMarketMsg {
+                initial_liquidity: Uint128::new(liquidity),
+                ..market_msg()
+            })
+        };
+        let info = message_info(&Addr::unchecked("creator"), &coins(50, "USDC"));
+        let err = execute(deps.as_mut(), mock_env(), info, create(0)).unwrap_err();
+        assert_eq!(err, ContractError::ZeroLiquidity {});
+
+        // ATOM markets need more liquidity than the default minimum
+        let info = message_info(&Addr::unchecked("creator"), &coins(1050, "ATOM"));
+        let err = execute(deps.as_mut(), mock_env(), info, create(1000)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::LiquidityOutOfRange {
+                min: Uint128::new(5000),
+                max: Uint128::MAX
+            }
+        );
+
+        // The fee and the deposit come on top of the liquidity, the fee is paid out
+        let info = message_info(&Addr::unchecked("creator"), &coins(1000, "USDC"));
+        let err = execute(deps.as_mut(), mock_env(), info, create(1000)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FundsMismatch {
+                expected: Uint128::new(1050),
+                received: Uint128::new(1000)
+            }
+        );
+        let info = message_info(&Addr::unchecked("creator"), &coins(1050, "USDC"));
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), create(1000)).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: treasury.to_string(),
+                amount: coins(10, "USDC"),
+            })]
+        );
+        execute(deps.as_mut(), mock_env(), info, create(1000)).unwrap();
+        ROLES
+            .save(
+                &mut deps.storage,
+                (Role::Resolver.as_str(), &Addr::unchecked("creator")),
+                &Empty {},
+            )
+            .unwrap();
+
+        // Only the traded market earns its deposit back
+        let (traded, idle) = (1, 2);
+        buy_yes(deps.as_mut(), traded);
+        for market_id in [traded, idle] {
+            resolve(deps.as_mut(), market_id, "YES");
+            finalize(deps.as_mut(), market_id);
+        }
+        assert_eq!(
+            claim_payout(deps.as_mut(), traded, "creator"),
+            Uint128::new(769 + 40)
+        );
+        assert_eq!(
+            claim_payout(deps.as_mut(), idle, "creator"),
+            Uint128::new(1000)
+        );
+
+        let msg = QueryMsg::KeeperPool {
+            denom: "USDC".to_string(),
+        };
+        let pool: KeeperPoolResponse =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(pool.balance, Uint128::new(40));
+
+        // Markets in a denom without a fee or deposit pay neither
+        let info = message_info(&Addr::unchecked("creator"), &coins(5000, "ATOM"));
+        let res = execute(deps.as_mut(), mock_env(), info, create(5000)).unwrap();
+        assert!(res.messages.is_empty());
+        let market = MARKETS.load(&deps.storage, 3).unwrap();
+        assert_eq!(market.spam_deposit, Uint128::zero());
+    }
+
+    #[test]
+    fn test_position_and_open_interest_limits() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+        let market_id = create_market(
+            deps.as_mut(),
+            CreateMarketMsg {
+                max_position: Some(PositionLimit::Collateral {
+                    amount: Uint128::new(400),
+                }),
+                max_open_interest: Some(Uint128::new(500)),
+                ..market_msg()
+            },
+        );
+
+        let alice = deps.api.addr_make("alice");
+        let bob = deps.api.addr_make("bob");
+        let buy = |deps: DepsMut, trader: &Addr, amount: u128| {
+            let msg = ExecuteMsg::BuyShares {
+                market_id,
+                outcome: "YES".to_string(),
+                amount: Uint128::new(amount),
+            };
+            let info = message_info(trader, &coins(amount, "USDC"));
+            execute(deps, mock_env(), info, msg)
+        };
+        let headroom = |deps: Deps, trader: &Addr| -> HeadroomResponse {
+            let msg = QueryMsg::Headroom {
+                market_id,
+                address: trader.to_string(),
+            };
+            from_json(query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+
+        buy(deps.as_mut(), &alice, 300).unwrap();
+        let room = headroom(deps.as_ref(), &alice);
+        assert_eq!(room.position_headroom, Some(Uint128::new(100)));
+        assert_eq!(room.open_interest, Uint128::new(300));
+        assert_eq!(room.open_interest_headroom, Some(Uint128::new(200)));
+
+        let err = buy(deps.as_mut(), &alice, 150).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::PositionLimitExceeded {
+                market_id,
+                limit: Uint128::new(400),
+                position: Uint128::new(450)
+            }
+        );
+        let err = buy(deps.as_mut(), &bob, 250).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::OpenInterestLimitExceeded {
+                market_id,
+                limit: Uint128::new(500),
+                open_interest: Uint128::new(550)
+            }
+        );
+
+        // Only the owner can lift the limits
+        let msg = ExecuteMsg::SetMarketLimits {
+            market_id,
+            max_position: None,
+            max_open_interest: None,
+        };
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        buy(deps.as_mut(), &bob, 250).unwrap();
+        let room = headroom(deps.as_ref(), &bob);
+        assert_eq!(room.position_headroom, None);
+        assert_eq!(room.open_interest_headroom, None);
+    }
+
+    #[test]
+    fn test_emergency_shutdown_exits_at_last_prices() {
+        // The 1000 USDC of liquidity and the 300 USDC buy
+        let mut deps = mock_dependencies_with_balance(&coins(1300, "USDC"));
+        let market_id = setup_market(deps.as_mut());
+        buy_yes(deps.as_mut(), market_id);
+
+        let msg = ExecuteMsg::EmergencyShutdown {
+            market_id: Some(market_id),
+            start_after: None,
+            limit: None,
+        };
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MarketSettled {
+                market_id,
+                status: MarketStatus::Voided
+            }
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetMarket { market_id }).unwrap();
+        let market: MarketResponse = from_json(res).unwrap();
+        assert!(market.shutdown);
+        assert_eq!(market.status, MarketStatus::Voided);
+
+        // 531 YES * 0.62832286, and the pool of 769 YES and 1300 NO to its provider
+        let mut redeemed = claim_payout(deps.as_mut(), market_id, "trader");
+        assert_eq!(redeemed, Uint128::new(333));
+        redeemed += claim_payout(deps.as_mut(), market_id, "creator");
+        // Rounding leaves a unit behind
+        assert_eq!(redeemed, Uint128::new(1299));
+    }
+
+    #[test]
+    fn test_emergency_shutdown_scales_down_to_market_collateral() {
+        // The balance backs both markets in full
+        let mut deps = mock_dependencies_with_balance(&coins(2300, "USDC"));
+        let first = setup_market(deps.as_mut());
+        let second = create_market(deps.as_mut(), market_msg());
+        buy_yes(deps.as_mut(), first);
+
+        // The first market is left with half of the 1300 USDC it took in
+        let mut market = MARKETS.load(&deps.storage, first).unwrap();
+        market.collateral = Uint128::new(650);
+        MARKETS.save(&mut deps.storage, first, &market).unwrap();
+
+        // One market per page
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        let page = |start_after| ExecuteMsg::EmergencyShutdown {
+            market_id: None,
+            start_after,
+            limit: Some(1),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), page(None)).unwrap();
+        assert_eq!(res.attributes[2], ("shut_down", "1"));
+        assert_eq!(res.attributes[3], ("last_market_id", "1"));
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), page(Some(first))).unwrap();
+        assert_eq!(res.attributes[2], ("shut_down", "2"));
+        let res = execute(deps.as_mut(), mock_env(), info, page(Some(second))).unwrap();
+        assert_eq!(res.attributes[2], ("shut_down", ""));
+
+        // The first market takes a haircut of 650 / 1299 without drawing on
+        // the collateral behind the second
+        let trader = claim_payout(deps.as_mut(), first, "trader");
+        let first_pool = claim_payout(deps.as_mut(), first, "creator");
+        let second_pool = claim_payout(deps.as_mut(), second, "creator");
+        assert_eq!(trader, Uint128::new(166));
+        assert_eq!(first_pool, Uint128::new(482));
+        assert_eq!(second_pool, Uint128::new(1000));
+    }
+
+    #[test]
+    fn test_transfer_shares() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+        buy_yes(deps.as_mut(), market_id);
+
+        let recipient = deps.api.addr_make("recipient");
+        let transfer = |amount: u128| ExecuteMsg::TransferShares {
+            market_id,
+            recipient: recipient.to_string(),
+            outcome: "YES".to_string(),
+            amount: Uint128::new(amount),
+        };
+        let info = message_info(&Addr::unchecked("trader"), &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), transfer(200)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info, transfer(400)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientShares {
+                available: Uint128::new(331),
+                required: Uint128::new(400)
+            }
+        );
+
+        let sender = BUYERS
+            .load(&deps.storage, (market_id, Addr::unchecked("trader")))
+            .unwrap();
+        let receiver = BUYERS
+            .load(&deps.storage, (market_id, recipient.clone()))
+            .unwrap();
+        assert_eq!(sender.shares_yes, Uint128::new(331));
+        assert_eq!(receiver.shares_yes, Uint128::new(200));
+
+        // The recipient's position is held to the market limits
+        let msg = ExecuteMsg::SetMarketLimits {
+            market_id,
+            max_position: Some(PositionLimit::Shares {
+                amount: Uint128::new(300),
+            }),
+            max_open_interest: None,
+        };
+        let owner = message_info(&Addr::unchecked("owner"), &[]);
+        execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+        let info = message_info(&Addr::unchecked("trader"), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, transfer(150)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::PositionLimitExceeded {
+                market_id,
+                limit: Uint128::new(300),
+                position: Uint128::new(350)
+            }
+        );
+    }
+
+    #[test]
+    fn test_private_market_participants() {
+        let mut deps = mock_dependencies();
+        let public = setup_market(deps.as_mut());
+        let private = create_market(
+            deps.as_mut(),
+            CreateMarketMsg {
+                visibility: Some(Visibility::Private),
+                ..market_msg()
+            },
+        );
+
+        let alice = deps.api.addr_make("alice");
+        let buy = ExecuteMsg::BuyShares {
+            market_id: private,
+            outcome: "YES".to_string(),
+            amount: Uint128::new(300),
+        };
+        let trader = message_info(&alice, &coins(300, "USDC"));
+        let err = execute(deps.as_mut(), mock_env(), trader.clone(), buy.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NotParticipant {
+                market_id: private,
+                address: alice.clone()
+            }
+        );
+
+        let add = |market_id| ExecuteMsg::AddParticipants {
+            market_id,
+            addresses: vec![alice.to_string()],
+        };
+        let err = execute(deps.as_mut(), mock_env(), trader.clone(), add(private)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let creator = message_info(&Addr::unchecked("creator"), &[]);
+        let err = execute(deps.as_mut(), mock_env(), creator.clone(), add(public)).unwrap_err();
+        assert_eq!(err, ContractError::MarketNotPrivate { market_id: public });
+        execute(deps.as_mut(), mock_env(), creator.clone(), add(private)).unwrap();
+        execute(deps.as_mut(), mock_env(), trader.clone(), buy.clone()).unwrap();
+
+        let remove = ExecuteMsg::RemoveParticipants {
+            market_id: private,
+            addresses: vec![alice.to_string()],
+        };
+        execute(deps.as_mut(), mock_env(), creator, remove).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), trader.clone(), buy).unwrap_err();
+        assert!(matches!(err, ContractError::NotParticipant { .. }));
+
+        // A removed participant can still sell what it holds
+        let sell = ExecuteMsg::SellShares {
+            market_id: private,
+            outcome: "YES".to_string(),
+            amount: Uint128::new(531),
+        };
+        let res = execute(deps.as_mut(), mock_env(), trader, sell).unwrap();
+        assert_eq!(res.attributes[4], ("usdc_received", "300"));
+        let position = BUYERS
+            .load(&deps.storage, (private, alice.clone()))
+            .unwrap();
+        assert_eq!(position.shares_yes, Uint128::zero());
+
+        // Private markets are only listed on request
+        let list = |include_private| -> Vec<u64> {
+            let msg = QueryMsg::ListMarkets {
+                start_after: None,
+                limit: None,
+                include_private,
+            };
+            let res: MarketsResponse =
+                from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            res.markets.iter().map(|market| market.market_id).collect()
+        };
+        assert_eq!(list(None), vec![public]);
+        assert_eq!(list(Some(true)), vec![public, private]);
+    }
+
+    #[test]
+    fn test_two_step_ownership_transfer() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+        let new_owner = deps.api.addr_make("new_owner");
+        let owner = message_info(&Addr::unchecked("owner"), &[]);
+
+        let propose = |expiry: Option<u64>| ExecuteMsg::ProposeNewOwner {
+            owner: new_owner.to_string(),
+            expiry: expiry.map(|seconds| env_after(seconds).block.time),
+        };
+        let info = message_info(&new_owner, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, propose(None)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // An offer that runs out cannot be accepted
+        execute(deps.as_mut(), mock_env(), owner.clone(), propose(Some(60))).unwrap();
+        let info = message_info(&new_owner, &[]);
+        let err = execute(
+            deps.as_mut(),
+            env_after(60),
+            info,
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::OwnershipTransferExpired {
+                expiry: env_after(60).block.time
+            }
+        );
+
+        let res = execute(deps.as_mut(), mock_env(), owner.clone(), propose(None)).unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("ownership_proposed")
+                .add_attribute("owner", "owner")
+                .add_attribute("pending_owner", new_owner.as_str())
+                .add_attribute("expiry", "none")]
+        );
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::PendingOwner {}).unwrap();
+        let pending: PendingOwnerResponse = from_json(res).unwrap();
+        assert_eq!(pending.pending_owner, Some(new_owner.clone()));
+        assert_eq!(pending.expiry, None);
+
+        // Only the proposed address can accept
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let info = message_info(&new_owner, &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("ownership_accepted")
+                .add_attribute("previous_owner", "owner")
+                .add_attribute("owner", new_owner.as_str())]
+        );
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.owner, Some(new_owner));
+        assert!(PENDING_OWNER.may_load(&deps.storage).unwrap().is_none());
+
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg::default());
+        let err = execute(deps.as_mut(), mock_env(), owner, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_renounce_ownership() {
+        let mut deps = mock_dependencies();
+        let owner = message_info(&Addr::unchecked("owner"), &[]);
+        instantiate(deps.as_mut(), mock_env(), owner.clone(), instantiate_msg()).unwrap();
+
+        let msg = ExecuteMsg::ProposeNewOwner {
+            owner: deps.api.addr_make("new_owner").to_string(),
+            expiry: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+        let msg = ExecuteMsg::RenounceOwnership {};
+        let res = execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("ownership_renounced").add_attribute("previous_owner", "owner")]
+        );
+
+        // The pending transfer goes with it and nobody is owner anymore
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.owner, None);
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::PendingOwner {}).unwrap();
+        let pending: PendingOwnerResponse = from_json(res).unwrap();
+        assert_eq!(pending.pending_owner, None);
+
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg::default());
+        let err = execute(deps.as_mut(), mock_env(), owner, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_roles_gate_creation_resolution_and_fees() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            open_market_creation: false,
+            ..instantiate_msg()
+        };
+        let owner = message_info(&Addr::unchecked("owner"), &[]);
+        instantiate(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+        let curator = deps.api.addr_make("curator");
+        let resolver = deps.api.addr_make("resolver");
+        let fee_manager = deps.api.addr_make("fee_manager");
+
+        let create = ExecuteMsg::CreateMarket(market_msg());
+        let info = message_info(&curator, &coins(1000, "USDC"));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), create.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        for (role, address) in [
+            (Role::MarketCreator, &curator),
+            (Role::Resolver, &resolver),
+            (Role::FeeManager, &fee_manager),
+        ] {
+            let msg = ExecuteMsg::GrantRole {
+                role,
+                address: address.to_string(),
+            };
+            execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+        }
+        execute(deps.as_mut(), mock_env(), info, create).unwrap();
+
+        let msg = QueryMsg::RoleMembers {
+            role: Role::MarketCreator,
+            start_after: None,
+            limit: None,
+        };
+        let res: RoleMembersResponse =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.members, vec![curator.clone()]);
+
+        // Only resolvers report, the creator included
+        let msg = ExecuteMsg::ResolveMarket {
+            market_id: 1,
+            winning_outcome: "YES".to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        for sender in [Addr::unchecked("anyone"), curator.clone()] {
+            let info = message_info(&sender, &[]);
+            let err = execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg.clone()).unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+        }
+        let info = message_info(&resolver, &[]);
+        execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap();
+
+        // Fee managers change fees and nothing else
+        let info = message_info(&fee_manager, &[]);
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            trading_fee_bps: Some(50),
+            ..UpdateConfigMsg::default()
+        });
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            trading_fee_bps: Some(50),
+            open_market_creation: Some(true),
+            ..UpdateConfigMsg::default()
+        });
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let msg = ExecuteMsg::RevokeRole {
+            role: Role::FeeManager,
+            address: fee_manager.to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+        let msg = QueryMsg::HasRole {
+            role: Role::FeeManager,
+            address: fee_manager.to_string(),
+        };
+        let res: HasRoleResponse =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert!(!res.has_role);
+    }
+
+    fn legacy_market(resolved: bool, winning_outcome: Option<&str>) -> LegacyMarket {
+        LegacyMarket {
+            creator: Addr::unchecked("creator"),
+            description: "Will it rain tomorrow?".to_string(),
+            shares_yes: Uint128::new(769),
+            shares_no: Uint128::new(1300),
+            total_liquidity: Uint128::new(1000),
+            total_liquidity_shares: Uint128::new(1_000_000),
+            resolved,
+            winning_outcome: winning_outcome.map(str::to_string),
+            price_yes: Uint128::new(62_834_224),
+            price_no: Uint128::new(37_165_775),
+        }
+    }
+
+    #[test]
+    fn test_migrate_converts_v0_1_markets() {
+        // All a v0.1 instantiate stored was the contract version, its
+        // markets came with no position for the creator
+        let mut deps = mock_dependencies();
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.1.0").unwrap();
+        for (market_id, market) in [
+            (1, legacy_market(false, None)),
+            (2, legacy_market(true, Some("NO"))),
+            (3, legacy_market(true, None)),
+        ] {
+            LEGACY_MARKETS
+                .save(&mut deps.storage, market_id, &market)
+                .unwrap();
+        }
+        MARKET_COUNT.save(&mut deps.storage, &3).unwrap();
+        // A v0.1 AddLiquidity of 200 into the first market
+        let provider = Addr::unchecked("provider");
+        let mut market = legacy_market(false, None);
+        market.total_liquidity += Uint128::new(200);
+        LEGACY_MARKETS.save(&mut deps.storage, 1, &market).unwrap();
+        let position = LiquidityProvider {
+            address: provider.clone(),
+            contributed_liquidity: Uint128::new(200),
+        };
+        LIQUIDITY_PROVIDERS
+            .save(&mut deps.storage, (1, provider.clone()), &position)
+            .unwrap();
+
+        // The old deployment has no config nor owner to carry over
+        let msg = MigrateMsg {
+            owner: None,
+            config: Some(instantiate_msg()),
+            legacy_close_time: None,
+        };
+        let err = migrate(deps.as_mut(), mock_env(), msg).unwrap_err();
+        assert_eq!(err, ContractError::MissingConfig {});
+
+        let owner = deps.api.addr_make("owner");
+        let close_time = env_after(CLOSE_IN).block.time;
+        let msg = MigrateMsg {
+            owner: Some(owner.to_string()),
+            config: Some(instantiate_msg()),
+            legacy_close_time: Some(close_time),
+        };
+        let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(res
+            .attributes
+            .contains(&Attribute::new("migrated_markets", "3")));
+        assert_eq!(
+            get_contract_version(&deps.storage).unwrap().version,
+            CONTRACT_VERSION
+        );
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.owner, Some(owner));
+        let open = MARKETS.load(&deps.storage, 1).unwrap();
+        assert_eq!(open.status, MarketStatus::Open);
+        assert_eq!(open.close_time, close_time);
+        assert_eq!(open.collateral_denom, "USDC");
+        assert_eq!(open.shares_yes, Uint128::new(769));
+        assert_eq!(open.price_yes, Uint128::new(62_834_224));
+        let resolved = MARKETS.load(&deps.storage, 2).unwrap();
+        assert_eq!(resolved.status, MarketStatus::Finalized);
+        assert_eq!(resolved.winning_outcome, Some("NO".to_string()));
+        let voided = MARKETS.load(&deps.storage, 3).unwrap();
+        assert_eq!(voided.status, MarketStatus::Voided);
+
+        // The creator's initial liquidity is theirs to withdraw
+        let creator = LIQUIDITY_PROVIDERS
+            .load(&deps.storage, (1, Addr::unchecked("creator")))
+            .unwrap();
+        assert_eq!(creator.contributed_liquidity, Uint128::new(1000));
+        let msg = ExecuteMsg::RemoveLiquidity {
+            market_id: 1,
+            amount: Uint128::new(500),
+        };
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_refuses_downgrade_and_other_contracts() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+        let msg = MigrateMsg {
+            owner: None,
+            config: None,
+            legacy_close_time: None,
+        };
+
+        // Same version migrations leave the current layout alone
+        migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CannotDowngrade {
+                from: "99.0.0".to_string(),
+                to: CONTRACT_VERSION.to_string()
+            }
+        );
+
+        set_contract_version(&mut deps.storage, "crates.io:cw20-base", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::WrongContract {
+                contract: "crates.io:cw20-base".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_add_liquidity_to_unresolved_market() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+
+        // Add liquidity to the market
+        let liquidity_provider = Addr::unchecked("provider");
+        let amount = Uint128::new(500);
+        let msg = ExecuteMsg::AddLiquidity { market_id, amount };
+        let info = message_info(&liquidity_provider, &coins(500, "USDC"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Verify the response attributes
+        assert_eq!(
+            res.attributes,
             vec![
                 ("action", "add_liquidity"),
                 ("market_id", "1"),
@@ -662,127 +4939,1706 @@ mod tests {
             ]
         );
 
-        // Verify the updated market state
-        let market: Market = MARKETS.load(&deps.storage, market_id).unwrap();
-        assert_eq!(market.shares_yes, Uint128::new(1500)); // 1000 + 500
-        assert_eq!(market.shares_no, Uint128::new(1500)); // 1000 + 500
-        assert_eq!(market.total_liquidity, Uint128::new(1500)); // 1000 + 500
-        assert_eq!(market.price_yes, Uint128::from(50000000u128)); // 1500 / (1500 + 1500) * DECIMAL_PRECISION
-        assert_eq!(market.price_no, Uint128::from(50000000u128)); // 1500 / (1500 + 1500) * DECIMAL_PRECISION
+        // Verify the updated market state
+        let market: Market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.shares_yes, Uint128::new(1500)); // 1000 + 500
+        assert_eq!(market.shares_no, Uint128::new(1500)); // 1000 + 500
+        assert_eq!(market.total_liquidity, Uint128::new(1500)); // 1000 + 500
+        assert_eq!(market.price_yes, Uint128::from(50000000u128)); // 1500 / (1500 + 1500) * DECIMAL_PRECISION
+        assert_eq!(market.price_no, Uint128::from(50000000u128)); // 1500 / (1500 + 1500) * DECIMAL_PRECISION
+
+        // Verify the liquidity provider's contribution
+        let provider: LiquidityProvider = LIQUIDITY_PROVIDERS
+            .load(&deps.storage, (market_id, liquidity_provider.clone()))
+            .unwrap();
+        assert_eq!(provider.contributed_liquidity, Uint128::new(500));
+    }
+
+    #[test]
+    fn test_add_liquidity_with_zero_amount() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+
+        // Attempt to add zero liquidity
+        let liquidity_provider = Addr::unchecked("provider");
+        let amount = Uint128::zero();
+        let msg = ExecuteMsg::AddLiquidity { market_id, amount };
+        let info = message_info(&liquidity_provider, &coins(0, "USDC"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+
+        // Verify that the operation fails
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::Std(StdError::generic_err("Amount must be greater than zero"))
+        );
+    }
+
+    #[test]
+    fn test_buy_shares() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+
+        // Simulate trades to create unequal prices
+        let trader = Addr::unchecked("trader");
+        let trade_amount = Uint128::new(300);
+        let msg = ExecuteMsg::BuyShares {
+            market_id,
+            outcome: "YES".to_string(),
+            amount: trade_amount,
+        };
+        let info = message_info(&trader, &coins(300, "USDC"));
+        // Liquidity Value: 1300 USDC
+        // Outcomes YES share : 769.2307692308
+        // Outcomes NO share : 1300
+        // Outcomes YES price : 0.6283228613 =  (1300/(1300+769))
+        // Outcomes NO price : 0.3716771387 =  (769/(1300+769))
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let market: Market = MARKETS.load(&deps.storage, market_id).unwrap();
+
+        assert_eq!(market.shares_yes, Uint128::new(769));
+        assert_eq!(market.shares_no, Uint128::new(1300));
+        assert_eq!(market.price_yes, Uint128::from(62832286u128));
+        assert_eq!(market.price_no, Uint128::from(37167713u128));
+        // TODO: Verify the buyer's shares
+        // Bob shares YES: 1294 - 769 = 531
+        let buyer: Buyer = BUYERS
+            .load(&deps.storage, (market_id, trader.clone()))
+            .unwrap();
+        assert_eq!(buyer.shares_yes, Uint128::new(531));
+        assert_eq!(buyer.shares_no, Uint128::new(0));
+        // Bob shares NO: 0
+    }
+
+    #[test]
+    fn test_sell_shares() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+        buy_yes(deps.as_mut(), market_id);
+
+        let sell = |amount: u128| ExecuteMsg::SellShares {
+            market_id,
+            outcome: "YES".to_string(),
+            amount: Uint128::new(amount),
+        };
+        let info = message_info(&Addr::unchecked("trader"), &[]);
+
+        // Half the position: (769 + 265 - c) * (1300 - c) >= 769 * 1300 gives c = 158
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), sell(265)).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "trader".to_string(),
+                amount: coins(158, "USDC"),
+            })]
+        );
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.shares_yes, Uint128::new(876));
+        assert_eq!(market.shares_no, Uint128::new(1142));
+        assert!(market.shares_yes * market.shares_no >= Uint128::new(769 * 1300));
+
+        // Selling the rest brings the pool back to balance, 299 paid in total
+        // against 300 spent
+        let res = execute(deps.as_mut(), mock_env(), info, sell(266)).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "trader".to_string(),
+                amount: coins(141, "USDC"),
+            })]
+        );
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.shares_yes, Uint128::new(1001));
+        assert_eq!(market.shares_no, Uint128::new(1001));
+        let buyer = BUYERS
+            .load(&deps.storage, (market_id, Addr::unchecked("trader")))
+            .unwrap();
+        assert_eq!(buyer.shares_yes, Uint128::zero());
+    }
+
+    #[test]
+    fn test_remove_liquidity_pays_provider() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+        buy_yes(deps.as_mut(), market_id);
+
+        // Half the pool of 769 YES / 1300 NO: 384 full sets paid out and the
+        // 266 NO left over kept as a position
+        let creator = Addr::unchecked("creator");
+        let msg = ExecuteMsg::RemoveLiquidity {
+            market_id,
+            amount: Uint128::new(500),
+        };
+        let info = message_info(&creator, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: creator.to_string(),
+                amount: coins(384, "USDC"),
+            })]
+        );
+
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.shares_yes, Uint128::new(385));
+        assert_eq!(market.shares_no, Uint128::new(650));
+        assert_eq!(market.total_liquidity, Uint128::new(500));
+        let position = BUYERS.load(&deps.storage, (market_id, creator)).unwrap();
+        assert_eq!(position.shares_yes, Uint128::zero());
+        assert_eq!(position.shares_no, Uint128::new(266));
+
+        // The pool cannot be emptied while the market trades
+        let msg = ExecuteMsg::RemoveLiquidity {
+            market_id,
+            amount: Uint128::new(500),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    }
+
+    #[test]
+    fn test_add_liquidity_with_unequal_prices() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+
+        // Simulate trades to create unequal prices
+        let trader = Addr::unchecked("trader");
+        let trade_amount = Uint128::new(300);
+        let msg = ExecuteMsg::BuyShares {
+            market_id,
+            outcome: "YES".to_string(),
+            amount: trade_amount,
+        };
+        let info = message_info(&trader, &coins(300, "USDC"));
+        // Liquidity Value: 1300 USDC
+        // Outcomes YES share : 769.2307692308
+        // Outcomes NO share : 1300
+        // Outcomes YES price : 0.6282527881 =  (1300/(1300+769.2307692308))
+        // Outcomes NO price : 0.3717472119 =  (769.2307692308/(1300+769.2307692308))
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Add liquidity to the market with unequal prices
+        let liquidity_provider = Addr::unchecked("provider");
+        let amount = Uint128::new(1000);
+        let msg = ExecuteMsg::AddLiquidity { market_id, amount };
+        let info = message_info(&liquidity_provider, &coins(1000, "USDC"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Verify the response attributes
+        assert_eq!(
+            res.attributes,
+            vec![
+                ("action", "add_liquidity"),
+                ("market_id", "1"),
+                ("liquidity_added", "1000"),
+            ]
+        );
+
+        // Verify the updated market state
+        let market: Market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.shares_yes, Uint128::new(1360)); // Rebalanced shares = (Shares_no * price_no)/price_yes = ((1300+1000) * 0.3717472119) /0.6282527881 = 1,360.90 //TODO: round up???
+        assert_eq!(market.shares_no, Uint128::new(2300)); // Rebalanced share
+        assert_eq!(market.total_liquidity, Uint128::new(2000)); // 1000 + 500
+        assert_eq!(market.price_yes, Uint128::from(62832286u128)); // Recalculated price (same as before)
+        assert_eq!(market.price_no, Uint128::from(37167713u128)); // Recalculated price (same as before)
+
+        // Verify the liquidity provider's contribution
+        let provider: LiquidityProvider = LIQUIDITY_PROVIDERS
+            .load(&deps.storage, (market_id, liquidity_provider.clone()))
+            .unwrap();
+        assert_eq!(provider.contributed_liquidity, Uint128::new(500));
+    }
+
+    #[test]
+    fn test_create_market_rejects_invalid_times() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+        let now = mock_env().block.time;
+        let info = message_info(&Addr::unchecked("creator"), &coins(1000, "USDC"));
+
+        let msg = ExecuteMsg::CreateMarket(CreateMarketMsg {
+            close_time: now,
+            ..market_msg()
+        });
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidCloseTime {});
+
+        let msg = ExecuteMsg::CreateMarket(CreateMarketMsg {
+            resolution_time: now.plus_seconds(CLOSE_IN - 1),
+            ..market_msg()
+        });
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidResolutionTime {});
+    }
+
+    #[test]
+    fn test_market_lifecycle() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+        let creator = Addr::unchecked("creator");
+        let trader = Addr::unchecked("trader");
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetMarket { market_id }).unwrap();
+        let market: MarketResponse = from_json(res).unwrap();
+        assert_eq!(market.status, MarketStatus::Open);
+        assert_eq!(market.seconds_to_close, CLOSE_IN);
+        assert_eq!(market.seconds_to_resolution, RESOLVE_IN);
+
+        // Trading stops at the close time
+        let msg = ExecuteMsg::BuyShares {
+            market_id,
+            outcome: "YES".to_string(),
+            amount: Uint128::new(100),
+        };
+        let info = message_info(&trader, &coins(100, "USDC"));
+        let err = execute(deps.as_mut(), env_after(CLOSE_IN), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MarketNotOpen {
+                market_id,
+                status: MarketStatus::Closed
+            }
+        );
+
+        // Resolution is not possible while trading is open or before the resolution time
+        let msg = ExecuteMsg::ResolveMarket {
+            market_id,
+            winning_outcome: "YES".to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        let info = message_info(&creator, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidTransition {
+                from: MarketStatus::Open,
+                to: MarketStatus::Proposed
+            }
+        );
+        let err = execute(
+            deps.as_mut(),
+            env_after(CLOSE_IN),
+            info.clone(),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ResolutionTooEarly {
+                resolution_time: mock_env().block.time.plus_seconds(RESOLVE_IN)
+            }
+        );
+
+        // Finalizing requires a resolved market
+        let finalize = ExecuteMsg::FinalizeMarket { market_id };
+        let err = execute(
+            deps.as_mut(),
+            env_after(RESOLVE_IN),
+            info.clone(),
+            finalize.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidTransition {
+                from: MarketStatus::Closed,
+                to: MarketStatus::Finalized
+            }
+        );
+
+        execute(
+            deps.as_mut(),
+            env_after(RESOLVE_IN),
+            info.clone(),
+            msg.clone(),
+        )
+        .unwrap();
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.status, MarketStatus::Proposed);
+
+        // A resolved market cannot be resolved again
+        let err = execute(deps.as_mut(), env_after(RESOLVE_IN), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidTransition {
+                from: MarketStatus::Proposed,
+                to: MarketStatus::Proposed
+            }
+        );
+
+        // The outcome becomes final once it can no longer be disputed
+        let anyone = message_info(&Addr::unchecked("anyone"), &[]);
+        let err = execute(
+            deps.as_mut(),
+            env_after(RESOLVE_IN),
+            anyone.clone(),
+            finalize.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::LivenessNotExpired {
+                expires_at: mock_env().block.time.plus_seconds(RESOLVE_IN + LIVENESS)
+            }
+        );
+        execute(
+            deps.as_mut(),
+            env_after(RESOLVE_IN + LIVENESS),
+            anyone,
+            finalize,
+        )
+        .unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            env_after(RESOLVE_IN),
+            QueryMsg::ListMarkets {
+                start_after: None,
+                limit: None,
+                include_private: None,
+            },
+        )
+        .unwrap();
+        let markets: MarketsResponse = from_json(res).unwrap();
+        assert_eq!(markets.markets.len(), 1);
+        assert_eq!(markets.markets[0].status, MarketStatus::Finalized);
+        assert_eq!(markets.markets[0].seconds_to_close, 0);
+        assert_eq!(markets.markets[0].winning_outcome, Some("YES".to_string()));
+    }
+
+    #[test]
+    fn test_claim_after_finalization() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+        buy_yes(deps.as_mut(), market_id);
+
+        // Nothing can be claimed before the outcome is final
+        let info = message_info(&Addr::unchecked("trader"), &[]);
+        let err = execute(
+            deps.as_mut(),
+            env_after(RESOLVE_IN),
+            info,
+            ExecuteMsg::Claim { market_id },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MarketNotSettled {
+                market_id,
+                status: MarketStatus::Closed
+            }
+        );
+
+        resolve(deps.as_mut(), market_id, "YES");
+        finalize(deps.as_mut(), market_id);
+
+        // Winning shares pay one unit each, the pool's YES shares go to the creator
+        assert_eq!(
+            claim_payout(deps.as_mut(), market_id, "trader"),
+            Uint128::new(531)
+        );
+        assert_eq!(
+            claim_payout(deps.as_mut(), market_id, "creator"),
+            Uint128::new(769)
+        );
+
+        let info = message_info(&Addr::unchecked("trader"), &[]);
+        let err = execute(
+            deps.as_mut(),
+            env_after(RESOLVE_IN),
+            info,
+            ExecuteMsg::Claim { market_id },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+    }
+
+    #[test]
+    fn test_trades_after_event_time_are_reverted() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+        buy_yes(deps.as_mut(), market_id);
+
+        // Bought once the result was already known
+        let msg = ExecuteMsg::BuyShares {
+            market_id,
+            outcome: "YES".to_string(),
+            amount: Uint128::new(300),
+        };
+        let info = message_info(&Addr::unchecked("late"), &coins(300, "USDC"));
+        execute(deps.as_mut(), env_after(600), info, msg).unwrap();
+
+        let event_time = env_after(300).block.time;
+        // Neither ahead of the resolution nor before the market existed
+        for too_far in [
+            env_after(RESOLVE_IN + 1).block.time,
+            mock_env().block.time.minus_seconds(1),
+        ] {
+            let msg = ExecuteMsg::ResolveMarket {
+                market_id,
+                winning_outcome: "YES".to_string(),
+                evidence: vec![],
+                event_time: Some(too_far),
+            };
+            let info = message_info(&Addr::unchecked("creator"), &[]);
+            let err = execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap_err();
+            assert_eq!(err, ContractError::InvalidEventTime {});
+        }
+
+        let msg = ExecuteMsg::ResolveMarket {
+            market_id,
+            winning_outcome: "YES".to_string(),
+            evidence: vec![],
+            event_time: Some(event_time),
+        };
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap();
+        let res = finalize(deps.as_mut(), market_id);
+        assert!(res
+            .attributes
+            .contains(&Attribute::new("reverted_trades", "1")));
+
+        // The pool is back to where the last earlier trade left it
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.shares_yes, Uint128::new(769));
+        assert_eq!(market.shares_no, Uint128::new(1300));
+
+        // The late buyer is refunded, everyone else is paid as before
+        assert_eq!(
+            claim_payout(deps.as_mut(), market_id, "late"),
+            Uint128::new(300)
+        );
+        assert_eq!(
+            claim_payout(deps.as_mut(), market_id, "trader"),
+            Uint128::new(531)
+        );
+        assert_eq!(
+            claim_payout(deps.as_mut(), market_id, "creator"),
+            Uint128::new(769)
+        );
+    }
+
+    #[test]
+    fn test_event_time_is_left_to_resolvers_and_arbiter() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+        buy_yes(deps.as_mut(), market_id);
+        let msg = ExecuteMsg::BuyShares {
+            market_id,
+            outcome: "YES".to_string(),
+            amount: Uint128::new(300),
+        };
+        let info = message_info(&Addr::unchecked("late"), &coins(300, "USDC"));
+        execute(deps.as_mut(), env_after(600), info, msg).unwrap();
+
+        // A bonded proposer cannot have trades reverted
+        let propose = |event_time| ExecuteMsg::ProposeOutcome {
+            market_id,
+            outcome: "YES".to_string(),
+            evidence: vec![],
+            event_time,
+        };
+        let info = message_info(&Addr::unchecked("proposer"), &coins(BOND, "USDC"));
+        let err = execute(
+            deps.as_mut(),
+            env_after(RESOLVE_IN),
+            info.clone(),
+            propose(Some(mock_env().block.time)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env_after(RESOLVE_IN), info, propose(None)).unwrap();
+
+        let msg = ExecuteMsg::DisputeOutcome {
+            market_id,
+            outcome: "NO".to_string(),
+            evidence: vec![],
+        };
+        let info = message_info(&Addr::unchecked("disputer"), &coins(2 * BOND, "USDC"));
+        execute(deps.as_mut(), env_after(RESOLVE_IN + 60), info, msg).unwrap();
+
+        // The arbiter settles the outcome along with the event time
+        let msg = ExecuteMsg::ArbitrateDispute {
+            market_id,
+            outcome: "YES".to_string(),
+            evidence: vec![],
+            event_time: Some(env_after(300).block.time),
+        };
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        let res = execute(deps.as_mut(), env_after(RESOLVE_IN + 120), info, msg).unwrap();
+        assert!(res
+            .attributes
+            .contains(&Attribute::new("reverted_trades", "1")));
+        assert_eq!(
+            claim_payout(deps.as_mut(), market_id, "late"),
+            Uint128::new(300)
+        );
+    }
+
+    #[test]
+    fn test_resolve_rejects_unknown_outcome() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+
+        let msg = ExecuteMsg::ResolveMarket {
+            market_id,
+            winning_outcome: "MAYBE".to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        let err = execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidOutcome {
+                outcome: "MAYBE".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_market_pays_equal_split() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+        buy_yes(deps.as_mut(), market_id);
+
+        resolve(deps.as_mut(), market_id, "INVALID");
+        finalize(deps.as_mut(), market_id);
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.status, MarketStatus::Voided);
+
+        // 531 YES * 0.5
+        assert_eq!(
+            claim_payout(deps.as_mut(), market_id, "trader"),
+            Uint128::new(265)
+        );
+        // 769 YES * 0.5 + 1300 NO * 0.5
+        assert_eq!(
+            claim_payout(deps.as_mut(), market_id, "creator"),
+            Uint128::new(1034)
+        );
+    }
+
+    #[test]
+    fn test_invalid_market_pays_last_traded_prices() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+        let market_id = create_market(
+            deps.as_mut(),
+            CreateMarketMsg {
+                void_fallback: Some(VoidFallback::LastTradedPrices),
+                ..market_msg()
+            },
+        );
+        buy_yes(deps.as_mut(), market_id);
+
+        resolve(deps.as_mut(), market_id, "INVALID");
+        finalize(deps.as_mut(), market_id);
+
+        // 531 YES * 0.62832286
+        assert_eq!(
+            claim_payout(deps.as_mut(), market_id, "trader"),
+            Uint128::new(333)
+        );
+        // 769 YES * 0.62832286 + 1300 NO * 0.37167713
+        assert_eq!(
+            claim_payout(deps.as_mut(), market_id, "creator"),
+            Uint128::new(966)
+        );
+    }
+
+    #[test]
+    fn test_cancel_untouched_market() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+        let msg = ExecuteMsg::CancelMarket { market_id };
+
+        let info = message_info(&Addr::unchecked("anyone"), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: "creator".to_string(),
+                amount: coins(1000, "USDC"),
+            }
+            .into()
+        );
+        assert!(!MARKETS.has(&deps.storage, market_id));
+        assert!(!LIQUIDITY_PROVIDERS.has(&deps.storage, (market_id, Addr::unchecked("creator"))));
+    }
+
+    #[test]
+    fn test_cancel_grouped_market_leaves_group() {
+        let mut deps = mock_dependencies();
+        let (group_id, market_ids) = setup_event_group(deps.as_mut());
+
+        let msg = ExecuteMsg::CancelMarket {
+            market_id: market_ids[2],
+        };
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let group = EVENT_GROUPS.load(&deps.storage, group_id).unwrap();
+        assert_eq!(group.market_ids, market_ids[..2]);
+
+        // The rest of the group still settles together
+        resolve(deps.as_mut(), market_ids[1], "YES");
+        finalize(deps.as_mut(), market_ids[1]);
+        let market = MARKETS.load(&deps.storage, market_ids[0]).unwrap();
+        assert_eq!(market.winning_outcome, Some("NO".to_string()));
+    }
+
+    #[test]
+    fn test_cancel_traded_market_requires_owner() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+        buy_yes(deps.as_mut(), market_id);
+        let msg = ExecuteMsg::CancelMarket { market_id };
+
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::MarketHasActivity { market_id });
+
+        // The owner voids the market, positions are refunded through claims
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert!(res.messages.is_empty());
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.status, MarketStatus::Voided);
+        assert_eq!(
+            claim_payout(deps.as_mut(), market_id, "trader"),
+            Uint128::new(265)
+        );
+    }
+
+    #[test]
+    fn test_pause_blocks_trading_but_not_claims() {
+        let mut deps = mock_dependencies();
+        let market_id = setup_market(deps.as_mut());
+        buy_yes(deps.as_mut(), market_id);
+        let owner = message_info(&Addr::unchecked("owner"), &[]);
+        let buy = ExecuteMsg::BuyShares {
+            market_id,
+            outcome: "NO".to_string(),
+            amount: Uint128::new(100),
+        };
+        let trader = message_info(&Addr::unchecked("trader"), &coins(100, "USDC"));
+
+        let msg = ExecuteMsg::Pause {
+            market_id: Some(market_id),
+        };
+        let err = execute(deps.as_mut(), mock_env(), trader.clone(), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), trader.clone(), buy.clone()).unwrap_err();
+        assert_eq!(err, ContractError::MarketPaused { market_id });
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetMarket { market_id }).unwrap();
+        let market: MarketResponse = from_json(res).unwrap();
+        assert!(market.paused);
+
+        let msg = ExecuteMsg::Unpause {
+            market_id: Some(market_id),
+        };
+        execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+        execute(deps.as_mut(), mock_env(), trader.clone(), buy.clone()).unwrap();
+
+        // Pausers halt every market at once
+        let pauser = deps.api.addr_make("pauser");
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::Pauser,
+            address: pauser.to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+        let msg = ExecuteMsg::Pause { market_id: None };
+        execute(deps.as_mut(), mock_env(), message_info(&pauser, &[]), msg).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), trader, buy).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+        let msg = ExecuteMsg::AddLiquidity {
+            market_id,
+            amount: Uint128::new(100),
+        };
+        let info = message_info(&Addr::unchecked("provider"), &coins(100, "USDC"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert!(config.paused);
+
+        // A voided market still pays out
+        let msg = ExecuteMsg::CancelMarket { market_id };
+        execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+        assert!(!claim_payout(deps.as_mut(), market_id, "trader").is_zero());
+    }
+
+    #[test]
+    fn test_conditional_market_voided_when_parent_resolves_other_way() {
+        let mut deps = mock_dependencies();
+        let parent_id = setup_market(deps.as_mut());
+        let child_id = create_market(deps.as_mut(), conditional_msg(parent_id, "YES"));
+        buy_yes(deps.as_mut(), child_id);
+
+        // The child cannot resolve before its parent
+        let msg = ExecuteMsg::ResolveMarket {
+            market_id: child_id,
+            winning_outcome: "YES".to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        let err = execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ConditionNotMet {
+                market_id: parent_id,
+                outcome: "YES".to_string()
+            }
+        );
+
+        resolve(deps.as_mut(), parent_id, "NO");
+        let res = finalize(deps.as_mut(), parent_id);
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "voided_markets" && attr.value == child_id.to_string()));
+
+        let child = MARKETS.load(&deps.storage, child_id).unwrap();
+        assert_eq!(child.status, MarketStatus::Voided);
+        assert_eq!(
+            claim_payout(deps.as_mut(), child_id, "trader"),
+            Uint128::new(265)
+        );
+    }
+
+    #[test]
+    fn test_merge_conditional_positions_into_parent() {
+        let mut deps = mock_dependencies();
+        let parent_id = setup_market(deps.as_mut());
+        let child_id = create_market(deps.as_mut(), conditional_msg(parent_id, "YES"));
+        let trader = Addr::unchecked("trader");
+
+        // 531 YES, then 665 NO
+        buy_yes(deps.as_mut(), child_id);
+        let msg = ExecuteMsg::BuyShares {
+            market_id: child_id,
+            outcome: "NO".to_string(),
+            amount: Uint128::new(300),
+        };
+        let info = message_info(&trader, &coins(300, "USDC"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = message_info(&trader, &[]);
+        let msg = ExecuteMsg::MergePositions {
+            market_id: parent_id,
+            amount: Uint128::new(100),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NotConditional {
+                market_id: parent_id
+            }
+        );
+
+        let msg = ExecuteMsg::MergePositions {
+            market_id: child_id,
+            amount: Uint128::new(600),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientShares {
+                available: Uint128::new(531),
+                required: Uint128::new(600)
+            }
+        );
+
+        // A paused parent takes no merges
+        let owner = message_info(&Addr::unchecked("owner"), &[]);
+        let pause = ExecuteMsg::Pause {
+            market_id: Some(parent_id),
+        };
+        execute(deps.as_mut(), mock_env(), owner.clone(), pause).unwrap();
+        let msg = ExecuteMsg::MergePositions {
+            market_id: child_id,
+            amount: Uint128::new(500),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MarketPaused {
+                market_id: parent_id
+            }
+        );
+        let unpause = ExecuteMsg::Unpause {
+            market_id: Some(parent_id),
+        };
+        execute(deps.as_mut(), mock_env(), owner.clone(), unpause).unwrap();
+
+        // A private parent only takes merges from its participants
+        let mut parent = MARKETS.load(&deps.storage, parent_id).unwrap();
+        parent.visibility = Visibility::Private;
+        MARKETS.save(&mut deps.storage, parent_id, &parent).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NotParticipant {
+                market_id: parent_id,
+                address: trader.clone()
+            }
+        );
+        PARTICIPANTS
+            .save(&mut deps.storage, (parent_id, &trader), &Empty {})
+            .unwrap();
+
+        // The merged shares count toward the parent's open interest
+        let limit = |amount: u128| ExecuteMsg::SetMarketLimits {
+            market_id: parent_id,
+            max_position: None,
+            max_open_interest: Some(Uint128::new(amount)),
+        };
+        execute(deps.as_mut(), mock_env(), owner.clone(), limit(400)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::OpenInterestLimitExceeded {
+                market_id: parent_id,
+                limit: Uint128::new(400),
+                open_interest: Uint128::new(500)
+            }
+        );
+        execute(deps.as_mut(), mock_env(), owner, limit(500)).unwrap();
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let child_position = BUYERS
+            .load(&deps.storage, (child_id, trader.clone()))
+            .unwrap();
+        assert_eq!(child_position.shares_yes, Uint128::new(31));
+        assert_eq!(child_position.shares_no, Uint128::new(165));
+        let parent_position = BUYERS.load(&deps.storage, (parent_id, trader)).unwrap();
+        assert_eq!(parent_position.shares_yes, Uint128::new(500));
+        assert_eq!(parent_position.shares_no, Uint128::zero());
+    }
+
+    #[test]
+    fn test_convert_no_positions() {
+        let mut deps = mock_dependencies();
+        let (group_id, market_ids) = setup_event_group(deps.as_mut());
+        let trader = Addr::unchecked("trader");
+
+        // 531 NO in the first two markets
+        buy(deps.as_mut(), market_ids[0], "NO");
+        buy(deps.as_mut(), market_ids[1], "NO");
+
+        // YES in a private market needs the sender on its list
+        let mut market = MARKETS.load(&deps.storage, market_ids[2]).unwrap();
+        market.visibility = Visibility::Private;
+        MARKETS
+            .save(&mut deps.storage, market_ids[2], &market)
+            .unwrap();
+        let info = message_info(&trader, &[]);
+        let msg = ExecuteMsg::ConvertNoPositions {
+            group_id,
+            market_ids: vec![market_ids[0], market_ids[1]],
+            amount: Uint128::new(500),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NotParticipant {
+                market_id: market_ids[2],
+                address: trader.clone()
+            }
+        );
+        PARTICIPANTS
+            .save(&mut deps.storage, (market_ids[2], &trader), &Empty {})
+            .unwrap();
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Two NO positions give one unit of collateral and YES in the third market
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: "trader".to_string(),
+                amount: coins(500, "USDC"),
+            }
+            .into()
+        );
+        for market_id in &market_ids[..2] {
+            let position = BUYERS
+                .load(&deps.storage, (*market_id, trader.clone()))
+                .unwrap();
+            assert_eq!(position.shares_no, Uint128::new(31));
+        }
+        let position = BUYERS
+            .load(&deps.storage, (market_ids[2], trader.clone()))
+            .unwrap();
+        assert_eq!(position.shares_yes, Uint128::new(500));
+
+        // The converted markets still hold what their YES holders can claim,
+        // the YES handed out is backed by the group
+        for market_id in &market_ids[..2] {
+            let market = MARKETS.load(&deps.storage, *market_id).unwrap();
+            assert_eq!(market.collateral, Uint128::new(1300));
+            assert_eq!(market.open_interest, Uint128::zero());
+        }
+        let market = MARKETS.load(&deps.storage, market_ids[2]).unwrap();
+        assert_eq!(market.collateral, Uint128::new(1000));
+        assert_eq!(market.open_interest, Uint128::new(500));
+
+        let info = message_info(&trader, &[]);
+        let msg = ExecuteMsg::ConvertNoPositions {
+            group_id,
+            market_ids: vec![market_ids[0], 42],
+            amount: Uint128::new(10),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MarketNotInGroup {
+                market_id: 42,
+                group_id
+            }
+        );
+    }
+
+    #[test]
+    fn test_event_group_resolves_rest_no() {
+        let mut deps = mock_dependencies();
+        let (group_id, market_ids) = setup_event_group(deps.as_mut());
 
-        // Verify the liquidity provider's contribution
-        let provider: LiquidityProvider = LIQUIDITY_PROVIDERS
-            .load(&deps.storage, (market_id, liquidity_provider.clone()))
-            .unwrap();
-        assert_eq!(provider.contributed_liquidity, Uint128::new(500));
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetEventGroup { group_id },
+        )
+        .unwrap();
+        let group: EventGroupResponse = from_json(res).unwrap();
+        assert_eq!(group.markets.len(), 3);
+        assert_eq!(group.price_sum, Uint128::new(150_000_000));
+        assert!(!group.sums_to_one);
+
+        resolve(deps.as_mut(), market_ids[1], "YES");
+
+        // A second YES in the same group is rejected
+        let msg = ExecuteMsg::ResolveMarket {
+            market_id: market_ids[0],
+            winning_outcome: "YES".to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        let err = execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::GroupWinnerExists {
+                group_id,
+                market_id: market_ids[1]
+            }
+        );
+
+        finalize(deps.as_mut(), market_ids[1]);
+
+        for market_id in [market_ids[0], market_ids[2]] {
+            let market = MARKETS.load(&deps.storage, market_id).unwrap();
+            assert_eq!(market.status, MarketStatus::Finalized);
+            assert_eq!(market.winning_outcome, Some("NO".to_string()));
+        }
     }
 
     #[test]
-    fn test_add_liquidity_with_zero_amount() {
+    fn test_event_group_leaves_disputed_market_to_arbiter() {
+        let mut deps = mock_dependencies();
+        let (_, market_ids) = setup_event_group(deps.as_mut());
+        buy_yes(deps.as_mut(), market_ids[2]);
+
+        // The first market's NO is disputed for INVALID and waits for the arbiter
+        let msg = ExecuteMsg::ProposeOutcome {
+            market_id: market_ids[0],
+            outcome: "NO".to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        let info = message_info(&Addr::unchecked("proposer"), &coins(BOND, "USDC"));
+        execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap();
+        let msg = ExecuteMsg::DisputeOutcome {
+            market_id: market_ids[0],
+            outcome: "INVALID".to_string(),
+            evidence: vec![],
+        };
+        let info = message_info(&Addr::unchecked("disputer"), &coins(2 * BOND, "USDC"));
+        execute(deps.as_mut(), env_after(RESOLVE_IN + 60), info, msg).unwrap();
+
+        // The winner's event happened before the buy in the third market
+        let msg = ExecuteMsg::ResolveMarket {
+            market_id: market_ids[1],
+            winning_outcome: "YES".to_string(),
+            evidence: vec![],
+            event_time: Some(mock_env().block.time),
+        };
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap();
+        let res = finalize(deps.as_mut(), market_ids[1]);
+        assert!(res
+            .attributes
+            .contains(&Attribute::new("resolved_no", market_ids[2].to_string())));
+
+        let disputed = MARKETS.load(&deps.storage, market_ids[0]).unwrap();
+        assert_eq!(disputed.status, MarketStatus::Disputed);
+        let third = MARKETS.load(&deps.storage, market_ids[2]).unwrap();
+        assert_eq!(third.status, MarketStatus::Finalized);
+        assert_eq!(third.winning_outcome, Some("NO".to_string()));
+        // The late buy is refunded
+        assert_eq!(
+            claim_payout(deps.as_mut(), market_ids[2], "trader"),
+            Uint128::new(300)
+        );
+
+        // The arbiter settles the dispute and its stakes
+        let msg = ExecuteMsg::ArbitrateDispute {
+            market_id: market_ids[0],
+            outcome: "NO".to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        let res = execute(deps.as_mut(), env_after(RESOLVE_IN + 120), info, msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: "proposer".to_string(),
+                amount: coins(3 * BOND, "USDC"),
+            }
+            .into()
+        );
+        let disputed = MARKETS.load(&deps.storage, market_ids[0]).unwrap();
+        assert_eq!(disputed.status, MarketStatus::Finalized);
+        assert_eq!(disputed.winning_outcome, Some("NO".to_string()));
+    }
+
+    #[test]
+    fn test_undisputed_proposal_finalizes_after_liveness() {
         let mut deps = mock_dependencies();
         let market_id = setup_market(deps.as_mut());
+        let proposer = Addr::unchecked("proposer");
 
-        // Attempt to add zero liquidity
-        let liquidity_provider = Addr::unchecked("provider");
-        let amount = Uint128::zero();
-        let msg = ExecuteMsg::AddLiquidity { market_id, amount };
-        let info = mock_info(liquidity_provider.as_str(), &coins(0, "USDC"));
-        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        // Proposals need the bond and a closed market
+        let msg = ExecuteMsg::ProposeOutcome {
+            market_id,
+            outcome: "YES".to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        let info = message_info(&proposer, &coins(BOND, "USDC"));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidTransition {
+                from: MarketStatus::Open,
+                to: MarketStatus::Proposed
+            }
+        );
+        let short = message_info(&proposer, &coins(BOND - 1, "USDC"));
+        let err = execute(deps.as_mut(), env_after(RESOLVE_IN), short, msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FundsMismatch {
+                expected: Uint128::new(BOND),
+                received: Uint128::new(BOND - 1)
+            }
+        );
+        execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap();
 
-        // Verify that the operation fails
+        let finalize = ExecuteMsg::FinalizeMarket { market_id };
+        let anyone = message_info(&Addr::unchecked("anyone"), &[]);
+        let err = execute(
+            deps.as_mut(),
+            env_after(RESOLVE_IN + LIVENESS - 1),
+            anyone.clone(),
+            finalize.clone(),
+        )
+        .unwrap_err();
         assert_eq!(
-            res.unwrap_err(),
-            ContractError::Std(StdError::generic_err("Amount must be greater than zero"))
+            err,
+            ContractError::LivenessNotExpired {
+                expires_at: mock_env().block.time.plus_seconds(RESOLVE_IN + LIVENESS)
+            }
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            env_after(RESOLVE_IN + LIVENESS),
+            anyone,
+            finalize,
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: "proposer".to_string(),
+                amount: coins(BOND, "USDC"),
+            }
+            .into()
         );
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.status, MarketStatus::Finalized);
+        assert_eq!(market.winning_outcome, Some("YES".to_string()));
     }
 
     #[test]
-    fn test_buy_shares() {
+    fn test_disputed_proposal_goes_to_arbiter() {
         let mut deps = mock_dependencies();
         let market_id = setup_market(deps.as_mut());
 
-        // Simulate trades to create unequal prices
-        let trader = Addr::unchecked("trader");
-        let trade_amount = Uint128::new(300);
-        let msg = ExecuteMsg::BuyShares {
+        let msg = ExecuteMsg::ProposeOutcome {
             market_id,
             outcome: "YES".to_string(),
-            amount: trade_amount,
+            evidence: vec![],
+            event_time: None,
         };
-        let info = mock_info(trader.as_str(), &coins(300, "USDC"));
-        // Liquidity Value: 1300 USDC
-        // Outcomes YES share : 769.2307692308
-        // Outcomes NO share : 1300
-        // Outcomes YES price : 0.6283228613 =  (1300/(1300+769))
-        // Outcomes NO price : 0.3716771387 =  (769/(1300+769))
-        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = message_info(&Addr::unchecked("proposer"), &coins(BOND, "USDC"));
+        execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap();
 
-        let market: Market = MARKETS.load(&deps.storage, market_id).unwrap();
+        let msg = ExecuteMsg::DisputeOutcome {
+            market_id,
+            outcome: "NO".to_string(),
+            evidence: vec![],
+        };
+        let info = message_info(&Addr::unchecked("disputer"), &coins(2 * BOND, "USDC"));
+        execute(deps.as_mut(), env_after(RESOLVE_IN + 60), info, msg).unwrap();
 
-        assert_eq!(market.shares_yes, Uint128::new(769));
-        assert_eq!(market.shares_no, Uint128::new(1300));
-        assert_eq!(market.price_yes, Uint128::from(62832286u128));
-        assert_eq!(market.price_no, Uint128::from(37167713u128));
-        // TODO: Verify the buyer's shares
-        // Bob shares YES: 1294 - 769 = 531
-        let buyer: Buyer = BUYERS
-            .load(&deps.storage, (market_id, trader.clone()))
-            .unwrap();
-        assert_eq!(buyer.shares_yes, Uint128::new(531));
-        assert_eq!(buyer.shares_no, Uint128::new(0));
-        // Bob shares NO: 0
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProposal { market_id },
+        )
+        .unwrap();
+        let proposal: ProposalResponse = from_json(res).unwrap();
+        assert_eq!(proposal.round, 1);
+        assert_eq!(proposal.outcome, "NO");
+
+        // Disputes past the round limit do not finalize on their own
+        let msg = ExecuteMsg::FinalizeMarket { market_id };
+        let info = message_info(&Addr::unchecked("anyone"), &[]);
+        let err = execute(deps.as_mut(), env_after(RESOLVE_IN + LIVENESS), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidTransition {
+                from: MarketStatus::Disputed,
+                to: MarketStatus::Finalized
+            }
+        );
+
+        let msg = ExecuteMsg::ArbitrateDispute {
+            market_id,
+            outcome: "NO".to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        let info = message_info(&Addr::unchecked("proposer"), &[]);
+        let err = execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // The arbiter sides with the disputer, who takes both stakes
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        let res = execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: "disputer".to_string(),
+                amount: coins(3 * BOND, "USDC"),
+            }
+            .into()
+        );
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.status, MarketStatus::Finalized);
+        assert_eq!(market.winning_outcome, Some("NO".to_string()));
     }
 
     #[test]
-    fn test_add_liquidity_with_unequal_prices() {
+    fn test_resolution_history_records_each_step() {
         let mut deps = mock_dependencies();
         let market_id = setup_market(deps.as_mut());
 
-        // Simulate trades to create unequal prices
-        let trader = Addr::unchecked("trader");
-        let trade_amount = Uint128::new(300);
-        let msg = ExecuteMsg::BuyShares {
+        let msg = ExecuteMsg::ProposeOutcome {
             market_id,
             outcome: "YES".to_string(),
-            amount: trade_amount,
+            evidence: vec!["https://example.com/result".to_string()],
+            event_time: None,
         };
-        let info = mock_info(trader.as_str(), &coins(300, "USDC"));
-        // Liquidity Value: 1300 USDC
-        // Outcomes YES share : 769.2307692308
-        // Outcomes NO share : 1300
-        // Outcomes YES price : 0.6282527881 =  (1300/(1300+769.2307692308))
-        // Outcomes NO price : 0.3717472119 =  (769.2307692308/(1300+769.2307692308))
-        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = message_info(&Addr::unchecked("proposer"), &coins(BOND, "USDC"));
+        execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap();
 
-        // Add liquidity to the market with unequal prices
-        let liquidity_provider = Addr::unchecked("provider");
-        let amount = Uint128::new(1000);
-        let msg = ExecuteMsg::AddLiquidity { market_id, amount };
-        let info = mock_info(liquidity_provider.as_str(), &coins(1000, "USDC"));
-        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::DisputeOutcome {
+            market_id,
+            outcome: "NO".to_string(),
+            evidence: vec!["ipfs://bafy-correction".to_string()],
+        };
+        let info = message_info(&Addr::unchecked("disputer"), &coins(2 * BOND, "USDC"));
+        execute(deps.as_mut(), env_after(RESOLVE_IN + 60), info, msg).unwrap();
 
-        // Verify the response attributes
+        let msg = ExecuteMsg::ArbitrateDispute {
+            market_id,
+            outcome: "NO".to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        execute(deps.as_mut(), env_after(RESOLVE_IN + 120), info, msg).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ResolutionHistory { market_id },
+        )
+        .unwrap();
+        let history: ResolutionHistoryResponse = from_json(res).unwrap();
+        let steps: Vec<_> = history
+            .steps
+            .iter()
+            .map(|step| (step.action, step.actor.as_str(), step.outcome.as_deref()))
+            .collect();
         assert_eq!(
-            res.attributes,
+            steps,
             vec![
-                ("action", "add_liquidity"),
-                ("market_id", "1"),
-                ("liquidity_added", "1000"),
+                (ResolutionAction::Proposed, "proposer", Some("YES")),
+                (ResolutionAction::Disputed, "disputer", Some("NO")),
+                (ResolutionAction::Arbitrated, "owner", Some("NO")),
             ]
         );
+        assert_eq!(history.steps[1].evidence, vec!["ipfs://bafy-correction"]);
+        assert_eq!(history.steps[1].time, env_after(RESOLVE_IN + 60).block.time);
+        assert_eq!(history.steps[1].height, mock_env().block.height);
+    }
 
-        // Verify the updated market state
-        let market: Market = MARKETS.load(&deps.storage, market_id).unwrap();
-        assert_eq!(market.shares_yes, Uint128::new(1360)); // Rebalanced shares = (Shares_no * price_no)/price_yes = ((1300+1000) * 0.3717472119) /0.6282527881 = 1,360.90 //TODO: round up???
-        assert_eq!(market.shares_no, Uint128::new(2300)); // Rebalanced share
-        assert_eq!(market.total_liquidity, Uint128::new(2000)); // 1000 + 500
-        assert_eq!(market.price_yes, Uint128::from(62832286u128)); // Recalculated price (same as before)
-        assert_eq!(market.price_no, Uint128::from(37167713u128)); // Recalculated price (same as before)
+    #[test]
+    fn test_dispute_escalation_splits_losing_stakes() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            max_dispute_rounds: 3,
+            ..instantiate_msg()
+        };
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let market_id = create_market(deps.as_mut(), market_msg());
 
-        // Verify the liquidity provider's contribution
-        let provider: LiquidityProvider = LIQUIDITY_PROVIDERS
-            .load(&deps.storage, (market_id, liquidity_provider.clone()))
-            .unwrap();
-        assert_eq!(provider.contributed_liquidity, Uint128::new(500));
+        let msg = ExecuteMsg::ProposeOutcome {
+            market_id,
+            outcome: "YES".to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        let info = message_info(&Addr::unchecked("proposer"), &coins(BOND, "USDC"));
+        execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap();
+
+        // Each round flips the outcome for twice the previous stake
+        let dispute = |outcome: &str| ExecuteMsg::DisputeOutcome {
+            market_id,
+            outcome: outcome.to_string(),
+            evidence: vec![],
+        };
+        let info = message_info(&Addr::unchecked("disputer"), &coins(BOND, "USDC"));
+        let err = execute(deps.as_mut(), env_after(RESOLVE_IN), info, dispute("NO")).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FundsMismatch {
+                expected: Uint128::new(2 * BOND),
+                received: Uint128::new(BOND)
+            }
+        );
+        let info = message_info(&Addr::unchecked("disputer"), &coins(2 * BOND, "USDC"));
+        let err = execute(
+            deps.as_mut(),
+            env_after(RESOLVE_IN),
+            info.clone(),
+            dispute("YES"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SameOutcome {
+                outcome: "YES".to_string()
+            }
+        );
+        execute(deps.as_mut(), env_after(RESOLVE_IN), info, dispute("NO")).unwrap();
+        let info = message_info(&Addr::unchecked("backer"), &coins(4 * BOND, "USDC"));
+        execute(
+            deps.as_mut(),
+            env_after(RESOLVE_IN + 60),
+            info,
+            dispute("YES"),
+        )
+        .unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetDisputeRounds { market_id },
+        )
+        .unwrap();
+        let rounds: DisputeRoundsResponse = from_json(res).unwrap();
+        assert_eq!(rounds.rounds.len(), 3);
+        assert_eq!(rounds.rounds[2].staker, Addr::unchecked("backer"));
+        assert_eq!(rounds.tentative_outcome, "YES");
+        assert_eq!(rounds.next_stake, Some(Uint128::new(8 * BOND)));
+        assert_eq!(
+            rounds.expires_at,
+            mock_env()
+                .block
+                .time
+                .plus_seconds(RESOLVE_IN + 60 + LIVENESS)
+        );
+
+        // Undisputed past the window, YES stakers split the NO stake 100:400
+        let msg = ExecuteMsg::FinalizeMarket { market_id };
+        let info = message_info(&Addr::unchecked("anyone"), &[]);
+        let res = execute(
+            deps.as_mut(),
+            env_after(RESOLVE_IN + 60 + LIVENESS),
+            info,
+            msg,
+        )
+        .unwrap();
+        let payouts: Vec<CosmosMsg> = res.messages.into_iter().map(|sub| sub.msg).collect();
+        assert_eq!(
+            payouts,
+            vec![
+                BankMsg::Send {
+                    to_address: "proposer".to_string(),
+                    amount: coins(140, "USDC"),
+                }
+                .into(),
+                BankMsg::Send {
+                    to_address: "backer".to_string(),
+                    amount: coins(560, "USDC"),
+                }
+                .into(),
+            ]
+        );
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.winning_outcome, Some("YES".to_string()));
+    }
+
+    #[test]
+    fn test_creator_bond_returned_or_slashed() {
+        let mut deps = mock_dependencies();
+        let treasury = deps.api.addr_make("treasury");
+        let msg = InstantiateMsg {
+            creator_bond: Uint128::new(50),
+            treasury: Some(treasury.to_string()),
+            slash_treasury_bps: 5000,
+            ..instantiate_msg()
+        };
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // The bond comes on top of the initial liquidity
+        let creator = deps.api.addr_make("creator");
+        let create = ExecuteMsg::CreateMarket(market_msg());
+        let info = message_info(&creator, &coins(1000, "USDC"));
+        let err = execute(deps.as_mut(), mock_env(), info, create.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FundsMismatch {
+                expected: Uint128::new(1050),
+                received: Uint128::new(1000)
+            }
+        );
+        let info = message_info(&creator, &coins(1050, "USDC"));
+        execute(deps.as_mut(), mock_env(), info.clone(), create.clone()).unwrap();
+        execute(deps.as_mut(), mock_env(), info, create).unwrap();
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::Resolver,
+            address: creator.to_string(),
+        };
+        let owner = message_info(&Addr::unchecked("owner"), &[]);
+        execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+        let (upheld, overturned) = (1, 2);
+        for market_id in [upheld, overturned] {
+            let msg = ExecuteMsg::ResolveMarket {
+                market_id,
+                winning_outcome: "YES".to_string(),
+                evidence: vec![],
+                event_time: None,
+            };
+            let info = message_info(&creator, &[]);
+            execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap();
+        }
+
+        // Undisputed, the bond goes back with the finalization
+        let res = finalize(deps.as_mut(), upheld);
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: creator.to_string(),
+                amount: coins(50, "USDC"),
+            }
+            .into()
+        );
+
+        // Overturned, half the bond goes to the disputer and half to the treasury
+        let msg = ExecuteMsg::DisputeOutcome {
+            market_id: overturned,
+            outcome: "NO".to_string(),
+            evidence: vec![],
+        };
+        let info = message_info(&Addr::unchecked("disputer"), &coins(BOND, "USDC"));
+        execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap();
+        let msg = ExecuteMsg::ArbitrateDispute {
+            market_id: overturned,
+            outcome: "NO".to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        let res = execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap();
+        let payouts: Vec<CosmosMsg> = res.messages.into_iter().map(|sub| sub.msg).collect();
+        assert_eq!(
+            payouts,
+            vec![
+                BankMsg::Send {
+                    to_address: "disputer".to_string(),
+                    amount: coins(BOND + 25, "USDC"),
+                }
+                .into(),
+                BankMsg::Send {
+                    to_address: treasury.to_string(),
+                    amount: coins(25, "USDC"),
+                }
+                .into(),
+            ]
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CreatorReputation {
+                creator: creator.to_string(),
+            },
+        )
+        .unwrap();
+        let reputation: CreatorReputationResponse = from_json(res).unwrap();
+        assert_eq!(reputation.markets_created, 2);
+        assert_eq!(reputation.resolutions_upheld, 1);
+        assert_eq!(reputation.resolutions_overturned, 1);
+        assert_eq!(reputation.total_slashed, Uint128::new(50));
+    }
+
+    // 2-of-3 committee market created by "creator"
+    fn committee_market(deps: DepsMut, members: &[Addr]) -> u64 {
+        let msg = CreateMarketMsg {
+            resolver: Some(ResolverMsg::Committee {
+                members: members.iter().map(|member| member.to_string()).collect(),
+                threshold: 2,
+                timeout: LIVENESS,
+            }),
+            ..market_msg()
+        };
+        create_market(deps, msg)
+    }
+
+    fn vote(
+        deps: DepsMut,
+        market_id: u64,
+        voter: &Addr,
+        outcome: &str,
+    ) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::VoteOutcome {
+            market_id,
+            outcome: outcome.to_string(),
+            evidence: vec![],
+        };
+        execute(deps, env_after(RESOLVE_IN), message_info(voter, &[]), msg)
+    }
+
+    #[test]
+    fn test_committee_resolution() {
+        let mut deps = mock_dependencies();
+        setup_market(deps.as_mut());
+        let members: Vec<Addr> = ["alice", "bob", "carol"]
+            .iter()
+            .map(|name| deps.api.addr_make(name))
+            .collect();
+        let market_id = committee_market(deps.as_mut(), &members);
+
+        // The creator no longer decides the outcome
+        let msg = ExecuteMsg::ResolveMarket {
+            market_id,
+            winning_outcome: "YES".to_string(),
+            evidence: vec![],
+            event_time: None,
+        };
+        let info = message_info(&Addr::unchecked("creator"), &[]);
+        let err = execute(deps.as_mut(), env_after(RESOLVE_IN), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::ResolverMismatch { market_id });
+
+        let err = vote(deps.as_mut(), market_id, &Addr::unchecked("creator"), "YES").unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        vote(deps.as_mut(), market_id, &members[0], "YES").unwrap();
+        let err = vote(deps.as_mut(), market_id, &members[0], "YES").unwrap_err();
+        assert_eq!(err, ContractError::AlreadyVoted { market_id });
+        vote(deps.as_mut(), market_id, &members[1], "NO").unwrap();
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.winning_outcome, None);
+
+        // Second YES vote reaches the threshold
+        vote(deps.as_mut(), market_id, &members[2], "YES").unwrap();
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.status, MarketStatus::Resolved);
+        assert_eq!(market.winning_outcome, Some("YES".to_string()));
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetVotes { market_id }).unwrap();
+        let votes: VotesResponse = from_json(res).unwrap();
+        assert_eq!(votes.votes.len(), 3);
+        assert_eq!(
+            votes.tally,
+            vec![
+                OutcomeTally {
+                    outcome: "YES".to_string(),
+                    votes: 2
+                },
+                OutcomeTally {
+                    outcome: "NO".to_string(),
+                    votes: 1
+                },
+            ]
+        );
+        assert_eq!(votes.threshold, 2);
+    }
+
+    #[test]
+    fn test_committee_timeout_voids_market() {
+        let mut deps = mock_dependencies();
+        setup_market(deps.as_mut());
+        let members: Vec<Addr> = ["alice", "bob", "carol"]
+            .iter()
+            .map(|name| deps.api.addr_make(name))
+            .collect();
+        let market_id = committee_market(deps.as_mut(), &members);
+        vote(deps.as_mut(), market_id, &members[0], "YES").unwrap();
+
+        // Finalizing before the deadline is not possible
+        let msg = ExecuteMsg::FinalizeMarket { market_id };
+        let info = message_info(&Addr::unchecked("anyone"), &[]);
+        let err = execute(
+            deps.as_mut(),
+            env_after(RESOLVE_IN),
+            info.clone(),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidTransition {
+                from: MarketStatus::Closed,
+                to: MarketStatus::Finalized
+            }
+        );
+
+        let env = env_after(RESOLVE_IN + LIVENESS);
+        let msg_vote = ExecuteMsg::VoteOutcome {
+            market_id,
+            outcome: "YES".to_string(),
+            evidence: vec![],
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&members[1], &[]),
+            msg_vote,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::VotingClosed {
+                deadline: env.block.time
+            }
+        );
+
+        execute(deps.as_mut(), env, info, msg).unwrap();
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.status, MarketStatus::Voided);
+        assert_eq!(market.winning_outcome, Some("INVALID".to_string()));
+    }
+
+    #[test]
+    fn test_keeper_actions_pay_rewards_from_pool() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            keeper_reward: Uint128::new(10),
+            ..instantiate_msg()
+        };
+        let info = message_info(&Addr::unchecked("owner"), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let market_id = create_market(deps.as_mut(), market_msg());
+        let other_id = create_market(deps.as_mut(), market_msg());
+        buy_yes(deps.as_mut(), market_id);
+
+        let info = message_info(&Addr::unchecked("funder"), &coins(15, "USDC"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::FundKeeperPool {},
+        )
+        .unwrap();
+
+        // Both markets close, the second reward is cut to what is left in the pool
+        let keeper = message_info(&Addr::unchecked("keeper"), &[]);
+        let msg = ExecuteMsg::CloseExpired { limit: None };
+        let res = execute(deps.as_mut(), env_after(CLOSE_IN), keeper.clone(), msg).unwrap();
+        assert_eq!(res.attributes[1].value, "1,2");
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: "keeper".to_string(),
+                amount: coins(15, "USDC"),
+            }
+            .into()
+        );
+        let market = MARKETS.load(&deps.storage, market_id).unwrap();
+        assert_eq!(market.status, MarketStatus::Closed);
+
+        // Proposals are only finalized once their liveness has passed
+        resolve(deps.as_mut(), market_id, "YES");
+        resolve(deps.as_mut(), other_id, "NO");
+        let msg = ExecuteMsg::FinalizeResolved { limit: None };
+        let res = execute(deps.as_mut(), env_after(RESOLVE_IN), keeper.clone(), msg).unwrap();
+        assert_eq!(res.attributes[1].value, "");
+
+        let msg = SudoMsg::FinalizeResolved { limit: None };
+        let res = sudo(deps.as_mut(), env_after(RESOLVE_IN + LIVENESS), msg).unwrap();
+        assert_eq!(res.attributes[1].value, "1,2");
+        assert!(res.messages.is_empty());
+        let market = MARKETS.load(&deps.storage, other_id).unwrap();
+        assert_eq!(market.status, MarketStatus::Finalized);
+
+        // Claims are paid in batches until nobody is left
+        let msg = ExecuteMsg::ProcessClaims {
+            market_id,
+            limit: Some(1),
+        };
+        let res = execute(deps.as_mut(), mock_env(), keeper.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "trader".to_string(),
+                amount: coins(531, "USDC"),
+            })]
+        );
+        let res = execute(deps.as_mut(), mock_env(), keeper.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "creator".to_string(),
+                amount: coins(769, "USDC"),
+            })]
+        );
+        let err = execute(deps.as_mut(), mock_env(), keeper, msg).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
     }
 }
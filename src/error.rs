@@ -1,13 +1,221 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Addr, StdError, Timestamp, Uint128};
+use cw_utils::PaymentError;
 use thiserror::Error;
 
+use crate::compliance::ComplianceAction;
+use crate::state::MarketStatus;
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
     #[error("Unauthorized")]
     Unauthorized {},
-    // Add any other custom errors you like here.
-    // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
+
+    #[error("Cannot migrate from contract {contract}")]
+    WrongContract { contract: String },
+
+    #[error("Cannot migrate from version {from} down to {to}")]
+    CannotDowngrade { from: String, to: String },
+
+    #[error("Invalid contract version {version}")]
+    InvalidVersion { version: String },
+
+    #[error("Migrating a deployment without a config requires one in the migrate message")]
+    MissingConfig {},
+
+    #[error("Expected {expected} in funds, received {received}")]
+    FundsMismatch {
+        expected: Uint128,
+        received: Uint128,
+    },
+
+    #[error("Invalid outcome: {outcome}")]
+    InvalidOutcome { outcome: String },
+
+    #[error("Close time must be in the future")]
+    InvalidCloseTime {},
+
+    #[error("Resolution time must not be before the close time")]
+    InvalidResolutionTime {},
+
+    #[error("Position of {position} in market {market_id} would exceed the limit of {limit}")]
+    PositionLimitExceeded {
+        market_id: u64,
+        limit: Uint128,
+        position: Uint128,
+    },
+
+    #[error(
+        "Open interest of {open_interest} in market {market_id} would exceed the limit of {limit}"
+    )]
+    OpenInterestLimitExceeded {
+        market_id: u64,
+        limit: Uint128,
+        open_interest: Uint128,
+    },
+
+    #[error("Initial liquidity must be greater than zero")]
+    ZeroLiquidity {},
+
+    #[error("Initial liquidity must be between {min} and {max}")]
+    LiquidityOutOfRange { min: Uint128, max: Uint128 },
+
+    #[error("Minimum liquidity cannot exceed the maximum")]
+    InvalidLiquidityRange {},
+
+    #[error("Basis points cannot exceed 10000, got {bps}")]
+    InvalidBps { bps: u64 },
+
+    #[error("Market {market_id} is {status}, trading and liquidity changes are not allowed")]
+    MarketNotOpen {
+        market_id: u64,
+        status: MarketStatus,
+    },
+
+    #[error("Market cannot move from {from} to {to}")]
+    InvalidTransition {
+        from: MarketStatus,
+        to: MarketStatus,
+    },
+
+    #[error("Market cannot be resolved before {resolution_time}")]
+    ResolutionTooEarly { resolution_time: Timestamp },
+
+    #[error("Market {market_id} is {status}, payouts are not available yet")]
+    MarketNotSettled {
+        market_id: u64,
+        status: MarketStatus,
+    },
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("No ownership transfer is pending")]
+    NoPendingOwner {},
+
+    #[error("Ownership transfer expired at {expiry}")]
+    OwnershipTransferExpired { expiry: Timestamp },
+
+    #[error("Market {market_id} already has trades or outside liquidity")]
+    MarketHasActivity { market_id: u64 },
+
+    #[error("Market {market_id} is {status} and cannot be used as a condition")]
+    InvalidCondition {
+        market_id: u64,
+        status: MarketStatus,
+    },
+
+    #[error("Collateral {denom} does not match the parent market's {expected}")]
+    CollateralMismatch { denom: String, expected: String },
+
+    #[error("Market {market_id} has not resolved to {outcome}")]
+    ConditionNotMet { market_id: u64, outcome: String },
+
+    #[error("Market {market_id} is not a conditional market")]
+    NotConditional { market_id: u64 },
+
+    #[error("Insufficient shares: {available} available, {required} required")]
+    InsufficientShares {
+        available: Uint128,
+        required: Uint128,
+    },
+
+    #[error("Trading is paused")]
+    Paused {},
+
+    #[error("Market {market_id} is paused")]
+    MarketPaused { market_id: u64 },
+
+    #[error("Market {market_id} is already {status}")]
+    MarketSettled {
+        market_id: u64,
+        status: MarketStatus,
+    },
+
+    #[error("An event group needs at least two distinct markets")]
+    InvalidEventGroup {},
+
+    #[error("Market {market_id} already belongs to event group {group_id}")]
+    MarketAlreadyGrouped { market_id: u64, group_id: u64 },
+
+    #[error("Market {market_id} is not part of event group {group_id}")]
+    MarketNotInGroup { market_id: u64, group_id: u64 },
+
+    #[error("Market {market_id} already resolved YES in event group {group_id}")]
+    GroupWinnerExists { group_id: u64, market_id: u64 },
+
+    #[error("Proposal can no longer be disputed since {expires_at}")]
+    LivenessExpired { expires_at: Timestamp },
+
+    #[error("Proposal can be disputed until {expires_at}")]
+    LivenessNotExpired { expires_at: Timestamp },
+
+    #[error("A committee needs distinct members and a threshold between 1 and their number")]
+    InvalidCommittee {},
+
+    #[error("Market {market_id} is not resolved this way")]
+    ResolverMismatch { market_id: u64 },
+
+    #[error("Already voted on market {market_id}")]
+    AlreadyVoted { market_id: u64 },
+
+    #[error("Voting closed at {deadline}")]
+    VotingClosed { deadline: Timestamp },
+
+    #[error("{address} is not allowed to {action} in market {market_id}")]
+    NotAllowed {
+        address: Addr,
+        action: ComplianceAction,
+        market_id: u64,
+    },
+
+    #[error("{address} is not a participant of private market {market_id}")]
+    NotParticipant { market_id: u64, address: Addr },
+
+    #[error("Market {market_id} is not private")]
+    MarketNotPrivate { market_id: u64 },
+
+    #[error("Oracle has not answered {key} yet")]
+    OracleNotAnswered { key: String },
+
+    #[error("No price feed is configured")]
+    PriceFeedNotConfigured {},
+
+    #[error("Oracle markets need at least one source and a tolerance of at most 10000 bps")]
+    InvalidOracleSources {},
+
+    #[error("Observation time must be between the close and resolution times")]
+    InvalidObservationTime {},
+
+    #[error("Event time must lie between the market's creation, a day before its close at the earliest, and now")]
+    InvalidEventTime {},
+
+    #[error("Price published at {publish_time} is outside the observation window")]
+    StalePrice { publish_time: i64 },
+
+    #[error("Price cannot be observed before {observation_time}")]
+    ObservationTooEarly { observation_time: Timestamp },
+
+    #[error("Price of market {market_id} was already observed")]
+    PriceAlreadyObserved { market_id: u64 },
+
+    #[error("Price confidence {conf} is too wide for price {price}")]
+    PriceTooUncertain { price: i64, conf: u64 },
+
+    #[error("Unusable price {price}")]
+    InvalidPrice { price: i64 },
+
+    #[error("Outcome is already {outcome}")]
+    SameOutcome { outcome: String },
+
+    #[error("Dispute reached its limit of {rounds} rounds and awaits the arbiter")]
+    RoundLimitReached { rounds: u32 },
+
+    #[error("Dispute can still escalate, the arbiter decides after {rounds} rounds")]
+    RoundLimitNotReached { rounds: u32 },
 }
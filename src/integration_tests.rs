@@ -0,0 +1,813 @@
+use std::str::FromStr;
+
+use cosmwasm_std::{coins, Addr, Decimal, Empty, Timestamp, Uint128};
+use cw_multi_test::error::AnyResult;
+use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+
+use crate::compliance::ComplianceAction;
+use crate::msg::{
+    CreateMarketMsg, DisputeRoundsResponse, ExecuteMsg, InstantiateMsg, MarketResponse,
+    OracleReportsResponse, OracleSourceMsg, QueryMsg, ResolverMsg, UpdateConfigMsg,
+};
+use crate::state::{Comparator, MarketCondition, MarketStatus};
+use crate::ContractError;
+
+const DENOM: &str = "USDC";
+const CLOSE_IN: u64 = 3600;
+const RESOLVE_IN: u64 = 7200;
+const MAX_PRICE_AGE: u64 = 600;
+
+/// Stand-in for an on-chain oracle: answers are set by anyone and served
+/// through the oracle query interface
+mod mock_oracle {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{
+        to_json_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+    };
+    use cw_storage_plus::Map;
+
+    use crate::oracle::{AnswerResponse, OracleQueryMsg};
+
+    const ANSWERS: Map<&str, String> = Map::new("answers");
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        SetAnswer { key: String, answer: String },
+    }
+
+    pub fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    pub fn execute(deps: DepsMut, _: Env, _: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+        let ExecuteMsg::SetAnswer { key, answer } = msg;
+        ANSWERS.save(deps.storage, &key, &answer)?;
+        Ok(Response::new())
+    }
+
+    pub fn query(deps: Deps, _: Env, msg: OracleQueryMsg) -> StdResult<Binary> {
+        let OracleQueryMsg::Answer { key } = msg;
+        to_json_binary(&AnswerResponse {
+            answer: ANSWERS.may_load(deps.storage, &key)?,
+        })
+    }
+}
+
+/// Stand-in for a Pyth-style price feed with prices pushed by anyone
+mod mock_price_feed {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{
+        to_json_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+    };
+    use cw_storage_plus::Map;
+
+    use crate::price_feed::{Price, PriceFeed, PriceFeedQueryMsg, PriceFeedResponse};
+
+    const PRICES: Map<&str, Price> = Map::new("prices");
+
+    /// Prices are published at the current block time, as a live feed would
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        SetPrice { id: String, price: i64, conf: u64 },
+    }
+
+    pub fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    pub fn execute(
+        deps: DepsMut,
+        env: Env,
+        _: MessageInfo,
+        msg: ExecuteMsg,
+    ) -> StdResult<Response> {
+        let ExecuteMsg::SetPrice { id, price, conf } = msg;
+        let price = Price {
+            price,
+            conf,
+            expo: -8,
+            publish_time: env.block.time.seconds() as i64,
+        };
+        PRICES.save(deps.storage, &id, &price)?;
+        Ok(Response::new())
+    }
+
+    pub fn query(deps: Deps, _: Env, msg: PriceFeedQueryMsg) -> StdResult<Binary> {
+        let PriceFeedQueryMsg::PriceFeed { id } = msg;
+        let price = PRICES.load(deps.storage, &id)?;
+        to_json_binary(&PriceFeedResponse {
+            price_feed: PriceFeed { id, price },
+        })
+    }
+}
+
+/// Stand-in for a compliance screen denying the addresses put on its list
+mod mock_compliance {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{
+        to_json_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+    };
+    use cw_storage_plus::Map;
+
+    use crate::compliance::{ComplianceQueryMsg, IsAllowedResponse};
+
+    const DENIED: Map<&str, Empty> = Map::new("denied");
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        Deny { address: String },
+    }
+
+    pub fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    pub fn execute(deps: DepsMut, _: Env, _: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+        let ExecuteMsg::Deny { address } = msg;
+        DENIED.save(deps.storage, &address, &Empty {})?;
+        Ok(Response::new())
+    }
+
+    pub fn query(deps: Deps, _: Env, msg: ComplianceQueryMsg) -> StdResult<Binary> {
+        let ComplianceQueryMsg::IsAllowed { address, .. } = msg;
+        to_json_binary(&IsAllowedResponse {
+            allowed: !DENIED.has(deps.storage, &address),
+        })
+    }
+}
+
+fn market_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    ))
+}
+
+fn oracle_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        mock_oracle::execute,
+        mock_oracle::instantiate,
+        mock_oracle::query,
+    ))
+}
+
+fn price_feed_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        mock_price_feed::execute,
+        mock_price_feed::instantiate,
+        mock_price_feed::query,
+    ))
+}
+
+fn compliance_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        mock_compliance::execute,
+        mock_compliance::instantiate,
+        mock_compliance::query,
+    ))
+}
+
+struct Suite {
+    app: App,
+    owner: Addr,
+    creator: Addr,
+    market_contract: Addr,
+    oracles: Vec<Addr>,
+    price_feeds: Vec<Addr>,
+}
+
+impl Suite {
+    fn new() -> Self {
+        let mut app = AppBuilder::new().build(|_, _, _| {});
+        let owner = app.api().addr_make("owner");
+        let creator = app.api().addr_make("creator");
+        app.init_modules(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &creator, coins(10_000, DENOM))
+                .unwrap()
+        });
+
+        let code_id = app.store_code(oracle_contract());
+        let oracles: Vec<Addr> = (0..3)
+            .map(|_| {
+                app.instantiate_contract(code_id, owner.clone(), &Empty {}, &[], "oracle", None)
+                    .unwrap()
+            })
+            .collect();
+
+        let code_id = app.store_code(price_feed_contract());
+        let price_feeds: Vec<Addr> = (0..3)
+            .map(|_| {
+                app.instantiate_contract(code_id, owner.clone(), &Empty {}, &[], "feed", None)
+                    .unwrap()
+            })
+            .collect();
+
+        let code_id = app.store_code(market_contract());
+        let market_contract = app
+            .instantiate_contract(
+                code_id,
+                owner.clone(),
+                &InstantiateMsg {
+                    arbiter: None,
+                    proposal_bond: Uint128::new(100),
+                    liveness: 3600,
+                    price_feeds: price_feeds.iter().map(Addr::to_string).collect(),
+                    max_price_age: MAX_PRICE_AGE,
+                    max_confidence_bps: 100,
+                    max_dispute_rounds: 3,
+                    creator_bond: Uint128::zero(),
+                    treasury: None,
+                    slash_treasury_bps: 0,
+                    keeper_reward: Uint128::zero(),
+                    default_collateral: DENOM.to_string(),
+                    trading_fee_bps: 0,
+                    min_liquidity: Uint128::one(),
+                    max_liquidity: None,
+                    collateral_min_liquidity: vec![],
+                    creation_fee: vec![],
+                    spam_deposit: vec![],
+                    min_activity: Uint128::zero(),
+                    open_market_creation: true,
+                    compliance: None,
+                },
+                &[],
+                "truth-markets",
+                None,
+            )
+            .unwrap();
+
+        Suite {
+            app,
+            owner,
+            creator,
+            market_contract,
+            oracles,
+            price_feeds,
+        }
+    }
+
+    fn now(&self) -> Timestamp {
+        self.app.block_info().time
+    }
+
+    fn advance(&mut self, seconds: u64) {
+        self.app
+            .update_block(|block| block.time = block.time.plus_seconds(seconds));
+    }
+
+    /// Oracle market answered by the first `sources` oracles
+    fn create_oracle_market(&mut self, query_key: &str, sources: usize, tolerance_bps: u64) -> u64 {
+        let resolver = ResolverMsg::Oracle {
+            sources: self.oracles[..sources]
+                .iter()
+                .map(|oracle| OracleSourceMsg {
+                    contract: oracle.to_string(),
+                    query_key: query_key.to_string(),
+                })
+                .collect(),
+            tolerance_bps,
+        };
+        self.create_market(resolver)
+    }
+
+    fn create_market(&mut self, resolver: ResolverMsg) -> u64 {
+        let msg = ExecuteMsg::CreateMarket(CreateMarketMsg {
+            description: "Will it rain tomorrow?".to_string(),
+            initial_liquidity: Uint128::new(1000),
+            close_time: self.now().plus_seconds(CLOSE_IN),
+            resolution_time: self.now().plus_seconds(RESOLVE_IN),
+            void_fallback: None,
+            condition: None,
+            resolver: Some(resolver),
+            max_position: None,
+            max_open_interest: None,
+            visibility: None,
+        });
+        let res = self
+            .app
+            .execute_contract(
+                self.creator.clone(),
+                self.market_contract.clone(),
+                &msg,
+                &coins(1000, DENOM),
+            )
+            .unwrap();
+        let attr = res
+            .events
+            .iter()
+            .flat_map(|event| &event.attributes)
+            .find(|attr| attr.key == "market_id")
+            .unwrap();
+        attr.value.parse().unwrap()
+    }
+
+    fn set_answer(&mut self, oracle: usize, key: &str, answer: &str) {
+        let msg = mock_oracle::ExecuteMsg::SetAnswer {
+            key: key.to_string(),
+            answer: answer.to_string(),
+        };
+        self.app
+            .execute_contract(self.owner.clone(), self.oracles[oracle].clone(), &msg, &[])
+            .unwrap();
+    }
+
+    /// Publishes the same price on every feed
+    fn set_prices(&mut self, id: &str, price: i64, conf: u64) {
+        for feed in 0..self.price_feeds.len() {
+            self.set_price(feed, id, price, conf);
+        }
+    }
+
+    fn set_price(&mut self, feed: usize, id: &str, price: i64, conf: u64) {
+        let msg = mock_price_feed::ExecuteMsg::SetPrice {
+            id: id.to_string(),
+            price,
+            conf,
+        };
+        self.app
+            .execute_contract(
+                self.owner.clone(),
+                self.price_feeds[feed].clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+    }
+
+    fn observe_price(&mut self, market_id: u64) -> AnyResult<()> {
+        let keeper = self.app.api().addr_make("keeper");
+        self.app
+            .execute_contract(
+                keeper,
+                self.market_contract.clone(),
+                &ExecuteMsg::ObservePrice { market_id },
+                &[],
+            )
+            .map(|_| ())
+    }
+
+    fn resolve_from_oracle(&mut self, market_id: u64) -> AnyResult<()> {
+        let keeper = self.app.api().addr_make("keeper");
+        self.app
+            .execute_contract(
+                keeper,
+                self.market_contract.clone(),
+                &ExecuteMsg::ResolveFromOracle { market_id },
+                &[],
+            )
+            .map(|_| ())
+    }
+
+    fn market(&self, market_id: u64) -> MarketResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.market_contract, &QueryMsg::GetMarket { market_id })
+            .unwrap()
+    }
+}
+
+#[test]
+fn oracle_answer_resolves_market() {
+    let mut suite = Suite::new();
+    let market_id = suite.create_oracle_market("rain-2026-10-19", 1, 0);
+
+    // Not before the resolution time
+    suite.advance(CLOSE_IN);
+    suite.set_answer(0, "rain-2026-10-19", "yes");
+    let err = suite.resolve_from_oracle(market_id).unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::ResolutionTooEarly { .. }
+    ));
+
+    suite.advance(RESOLVE_IN - CLOSE_IN);
+    suite.resolve_from_oracle(market_id).unwrap();
+    let market = suite.market(market_id);
+    assert_eq!(market.status, MarketStatus::Resolved);
+    assert_eq!(market.winning_outcome, Some("YES".to_string()));
+
+    // Finalize and let the creator withdraw its liquidity
+    let creator = suite.creator.clone();
+    let contract = suite.market_contract.clone();
+    suite
+        .app
+        .execute_contract(
+            creator.clone(),
+            contract.clone(),
+            &ExecuteMsg::FinalizeMarket { market_id },
+            &[],
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            creator.clone(),
+            contract,
+            &ExecuteMsg::Claim { market_id },
+            &[],
+        )
+        .unwrap();
+    let balance = suite.app.wrap().query_balance(&creator, DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(10_000));
+}
+
+#[test]
+fn unanswered_oracle_keeps_market_open_for_resolution() {
+    let mut suite = Suite::new();
+    let market_id = suite.create_oracle_market("rain-2026-10-19", 1, 0);
+    suite.advance(RESOLVE_IN);
+
+    let err = suite.resolve_from_oracle(market_id).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::OracleNotAnswered {
+            key: "rain-2026-10-19".to_string()
+        }
+    );
+
+    // An answer the market cannot map is rejected
+    suite.set_answer(0, "rain-2026-10-19", "maybe");
+    let err = suite.resolve_from_oracle(market_id).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidOutcome {
+            outcome: "MAYBE".to_string()
+        }
+    );
+
+    suite.set_answer(0, "rain-2026-10-19", "INVALID");
+    suite.resolve_from_oracle(market_id).unwrap();
+    assert_eq!(suite.market(market_id).status, MarketStatus::Voided);
+}
+
+#[test]
+fn price_market_resolves_from_feed() {
+    let mut suite = Suite::new();
+    let observation_time = suite.now().plus_seconds(CLOSE_IN);
+    let market_id = suite.create_market(ResolverMsg::PriceFeed {
+        asset: "ATOM/USD".to_string(),
+        comparator: Comparator::AtOrAbove,
+        threshold: Decimal::from_str("10").unwrap(),
+        observation_time,
+        tolerance_bps: 100,
+    });
+
+    // Published before the observation time
+    suite.advance(CLOSE_IN - 1);
+    suite.set_prices("ATOM/USD", 1_050_000_000, 1_000_000);
+    let err = suite.observe_price(market_id).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::ObservationTooEarly { observation_time }
+    );
+    suite.advance(1);
+    let err = suite.observe_price(market_id).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::StalePrice {
+            publish_time: observation_time.seconds() as i64 - 1
+        }
+    );
+
+    // 10.50 ± 0.20 is wider than the 1% allowed
+    suite.advance(60);
+    suite.set_prices("ATOM/USD", 1_050_000_000, 20_000_000);
+    let err = suite.observe_price(market_id).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::PriceTooUncertain {
+            price: 1_050_000_000,
+            conf: 20_000_000
+        }
+    );
+
+    suite.set_prices("ATOM/USD", 1_050_000_000, 1_000_000);
+    suite.observe_price(market_id).unwrap();
+
+    // Long after the window the recorded price still resolves the market,
+    // whatever the feeds show by then
+    suite.advance(RESOLVE_IN);
+    suite.set_prices("ATOM/USD", 900_000_000, 1_000_000);
+    suite.resolve_from_oracle(market_id).unwrap();
+    let market = suite.market(market_id);
+    assert_eq!(market.status, MarketStatus::Resolved);
+    assert_eq!(market.winning_outcome, Some("YES".to_string()));
+}
+
+#[test]
+fn unobserved_price_market_resolves_invalid() {
+    let mut suite = Suite::new();
+    let observation_time = suite.now().plus_seconds(CLOSE_IN);
+    let market_id = suite.create_market(ResolverMsg::PriceFeed {
+        asset: "ATOM/USD".to_string(),
+        comparator: Comparator::AtOrAbove,
+        threshold: Decimal::from_str("10").unwrap(),
+        observation_time,
+        tolerance_bps: 100,
+    });
+
+    // The window closed before anyone looked at the feeds
+    suite.advance(RESOLVE_IN);
+    suite.set_prices("ATOM/USD", 1_050_000_000, 1_000_000);
+    let err = suite.observe_price(market_id).unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::StalePrice { .. }
+    ));
+    suite.resolve_from_oracle(market_id).unwrap();
+    let market = suite.market(market_id);
+    assert_eq!(market.status, MarketStatus::Voided);
+    assert_eq!(market.winning_outcome, Some("INVALID".to_string()));
+}
+
+#[test]
+fn oracle_majority_within_tolerance_resolves() {
+    let mut suite = Suite::new();
+    // One dissenting source out of three is tolerated
+    let market_id = suite.create_oracle_market("rain-2026-10-19", 3, 3400);
+    suite.advance(RESOLVE_IN);
+    suite.set_answer(0, "rain-2026-10-19", "YES");
+    suite.set_answer(1, "rain-2026-10-19", "NO");
+    suite.set_answer(2, "rain-2026-10-19", "yes");
+
+    suite.resolve_from_oracle(market_id).unwrap();
+    let market = suite.market(market_id);
+    assert_eq!(market.status, MarketStatus::Resolved);
+    assert_eq!(market.winning_outcome, Some("YES".to_string()));
+
+    let reports: OracleReportsResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.market_contract,
+            &QueryMsg::GetOracleReports { market_id },
+        )
+        .unwrap();
+    let answers: Vec<(Addr, String)> = reports
+        .reports
+        .into_iter()
+        .map(|report| (report.source, report.answer))
+        .collect();
+    assert_eq!(
+        answers,
+        vec![
+            (suite.oracles[0].clone(), "YES".to_string()),
+            (suite.oracles[1].clone(), "NO".to_string()),
+            (suite.oracles[2].clone(), "YES".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn disagreeing_sources_open_a_dispute() {
+    let mut suite = Suite::new();
+    let market_id = suite.create_oracle_market("rain-2026-10-19", 3, 0);
+    suite.advance(RESOLVE_IN);
+    suite.set_answer(0, "rain-2026-10-19", "YES");
+    suite.set_answer(1, "rain-2026-10-19", "NO");
+    suite.set_answer(2, "rain-2026-10-19", "YES");
+
+    suite.resolve_from_oracle(market_id).unwrap();
+    let market = suite.market(market_id);
+    assert_eq!(market.status, MarketStatus::Disputed);
+    assert_eq!(market.winning_outcome, None);
+
+    // The majority answer is only tentative, the first dispute costs the bond
+    let rounds: DisputeRoundsResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.market_contract,
+            &QueryMsg::GetDisputeRounds { market_id },
+        )
+        .unwrap();
+    assert_eq!(rounds.tentative_outcome, "YES");
+    assert_eq!(rounds.next_stake, Some(Uint128::new(100)));
+
+    // Every source's report is kept, the dissenting one included
+    let reports: OracleReportsResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.market_contract,
+            &QueryMsg::GetOracleReports { market_id },
+        )
+        .unwrap();
+    let answers: Vec<&str> = reports
+        .reports
+        .iter()
+        .map(|report| report.answer.as_str())
+        .collect();
+    assert_eq!(answers, vec!["YES", "NO", "YES"]);
+
+    // A disputer backing NO carries the outcome once the window passes
+    let creator = suite.creator.clone();
+    let contract = suite.market_contract.clone();
+    suite
+        .app
+        .execute_contract(
+            creator.clone(),
+            contract.clone(),
+            &ExecuteMsg::DisputeOutcome {
+                market_id,
+                outcome: "NO".to_string(),
+                evidence: vec![],
+            },
+            &coins(100, DENOM),
+        )
+        .unwrap();
+    suite.advance(3600);
+    suite
+        .app
+        .execute_contract(
+            creator.clone(),
+            contract,
+            &ExecuteMsg::FinalizeMarket { market_id },
+            &[],
+        )
+        .unwrap();
+    let market = suite.market(market_id);
+    assert_eq!(market.status, MarketStatus::Finalized);
+    assert_eq!(market.winning_outcome, Some("NO".to_string()));
+    // The disputer's stake comes back, the liquidity is still to claim
+    let balance = suite.app.wrap().query_balance(&creator, DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(9_000));
+}
+
+#[test]
+fn price_market_uses_median_of_feeds() {
+    let mut suite = Suite::new();
+    let observation_time = suite.now().plus_seconds(CLOSE_IN);
+    let resolver = |tolerance_bps| ResolverMsg::PriceFeed {
+        asset: "ATOM/USD".to_string(),
+        comparator: Comparator::Above,
+        threshold: Decimal::from_str("10").unwrap(),
+        observation_time,
+        tolerance_bps,
+    };
+    let tight = suite.create_market(resolver(100));
+    let loose = suite.create_market(resolver(1000));
+
+    // 9.70, 10.10 and 10.20: the median is above 10 despite the low feed
+    suite.advance(CLOSE_IN + 60);
+    suite.set_price(0, "ATOM/USD", 970_000_000, 1_000_000);
+    suite.set_price(1, "ATOM/USD", 1_010_000_000, 1_000_000);
+    suite.set_price(2, "ATOM/USD", 1_020_000_000, 1_000_000);
+    suite.observe_price(loose).unwrap();
+    suite.observe_price(tight).unwrap();
+    suite.advance(RESOLVE_IN - CLOSE_IN - 60);
+
+    suite.resolve_from_oracle(loose).unwrap();
+    let market = suite.market(loose);
+    assert_eq!(market.status, MarketStatus::Resolved);
+    assert_eq!(market.winning_outcome, Some("YES".to_string()));
+
+    // A 0.50 spread is more than 1% of the median
+    suite.resolve_from_oracle(tight).unwrap();
+    assert_eq!(suite.market(tight).status, MarketStatus::Disputed);
+}
+
+#[test]
+fn compliance_contract_screens_trades_and_transfers() {
+    let mut suite = Suite::new();
+    let market_id = suite.create_oracle_market("rain-2026-10-19", 1, 0);
+    let (owner, creator) = (suite.owner.clone(), suite.creator.clone());
+    let contract = suite.market_contract.clone();
+    let bob = suite.app.api().addr_make("bob");
+
+    let code_id = suite.app.store_code(compliance_contract());
+    let compliance = suite
+        .app
+        .instantiate_contract(code_id, owner.clone(), &Empty {}, &[], "compliance", None)
+        .unwrap();
+    let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        compliance: Some(compliance.to_string()),
+        ..UpdateConfigMsg::default()
+    });
+    suite
+        .app
+        .execute_contract(owner.clone(), contract.clone(), &msg, &[])
+        .unwrap();
+
+    let buy = ExecuteMsg::BuyShares {
+        market_id,
+        outcome: "YES".to_string(),
+        amount: Uint128::new(300),
+    };
+    suite
+        .app
+        .execute_contract(creator.clone(), contract.clone(), &buy, &coins(300, DENOM))
+        .unwrap();
+
+    // A full set in a market conditional on this one
+    let msg = ExecuteMsg::CreateMarket(CreateMarketMsg {
+        description: "Will the harvest fail?".to_string(),
+        initial_liquidity: Uint128::new(1000),
+        close_time: suite.now().plus_seconds(CLOSE_IN),
+        resolution_time: suite.now().plus_seconds(RESOLVE_IN),
+        void_fallback: None,
+        condition: Some(MarketCondition {
+            market_id,
+            outcome: "YES".to_string(),
+        }),
+        resolver: None,
+        max_position: None,
+        max_open_interest: None,
+        visibility: None,
+    });
+    suite
+        .app
+        .execute_contract(creator.clone(), contract.clone(), &msg, &coins(1000, DENOM))
+        .unwrap();
+    let child_id = market_id + 1;
+    for outcome in ["YES", "NO"] {
+        let msg = ExecuteMsg::BuyShares {
+            market_id: child_id,
+            outcome: outcome.to_string(),
+            amount: Uint128::new(300),
+        };
+        suite
+            .app
+            .execute_contract(creator.clone(), contract.clone(), &msg, &coins(300, DENOM))
+            .unwrap();
+    }
+
+    // Shares can only go to addresses the screen lets through
+    let deny = |address: &Addr| mock_compliance::ExecuteMsg::Deny {
+        address: address.to_string(),
+    };
+    suite
+        .app
+        .execute_contract(owner.clone(), compliance.clone(), &deny(&bob), &[])
+        .unwrap();
+    let transfer = ExecuteMsg::TransferShares {
+        market_id,
+        recipient: bob.to_string(),
+        outcome: "YES".to_string(),
+        amount: Uint128::new(100),
+    };
+    let err = suite
+        .app
+        .execute_contract(creator.clone(), contract.clone(), &transfer, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NotAllowed {
+            address: bob,
+            action: ComplianceAction::TransferShares,
+            market_id
+        }
+    );
+
+    suite
+        .app
+        .execute_contract(owner.clone(), compliance, &deny(&creator), &[])
+        .unwrap();
+    let err = suite
+        .app
+        .execute_contract(creator.clone(), contract.clone(), &buy, &coins(300, DENOM))
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NotAllowed {
+            address: creator.clone(),
+            action: ComplianceAction::Buy,
+            market_id
+        }
+    );
+
+    // Nor can they be had by merging out of the conditional market
+    let merge = ExecuteMsg::MergePositions {
+        market_id: child_id,
+        amount: Uint128::new(100),
+    };
+    let err = suite
+        .app
+        .execute_contract(creator.clone(), contract.clone(), &merge, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NotAllowed {
+            address: creator.clone(),
+            action: ComplianceAction::Buy,
+            market_id
+        }
+    );
+
+    // Without a compliance contract nobody is screened
+    let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        compliance: Some(String::new()),
+        ..UpdateConfigMsg::default()
+    });
+    suite
+        .app
+        .execute_contract(owner, contract.clone(), &msg, &[])
+        .unwrap();
+    suite
+        .app
+        .execute_contract(creator, contract, &buy, &coins(300, DENOM))
+        .unwrap();
+}
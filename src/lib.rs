@@ -1,7 +1,14 @@
+pub mod compliance;
 pub mod contract;
 mod error;
 pub mod helpers;
+mod migrations;
 pub mod msg;
+pub mod oracle;
+pub mod price_feed;
 pub mod state;
 
+#[cfg(test)]
+mod integration_tests;
+
 pub use crate::error::ContractError;
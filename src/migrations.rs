@@ -0,0 +1,116 @@
+//! Storage migrations between contract versions that changed the layout.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Empty, Order, StdResult, Storage, Timestamp, Uint128};
+use cw_storage_plus::Map;
+
+use crate::state::{
+    LiquidityProvider, Market, MarketStatus, Resolver, Visibility, VoidFallback, CLOSE_QUEUE,
+    LIQUIDITY_PROVIDERS, MARKETS,
+};
+
+/// Market record of v0.1, before markets had a status, a schedule and a collateral
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LegacyMarket {
+    pub creator: Addr,
+    pub description: String,
+    pub shares_yes: Uint128,
+    pub shares_no: Uint128,
+    pub total_liquidity: Uint128,
+    pub total_liquidity_shares: Uint128,
+    pub resolved: bool,
+    pub winning_outcome: Option<String>,
+    pub price_yes: Uint128,
+    pub price_no: Uint128,
+}
+
+pub const LEGACY_MARKETS: Map<u64, LegacyMarket> = Map::new("markets");
+
+/// Rewrite v0.1 markets in the current layout. Resolved markets become final,
+/// or void without a YES or NO outcome. Unresolved ones stay open until
+/// `close_time`, which also serves as their resolution time. v0.1 kept no
+/// position for the creator's initial liquidity, it is credited to the
+/// creator as what the other providers do not account for. Returns the
+/// number of markets converted.
+pub fn migrate_v0_1_markets(
+    storage: &mut dyn Storage,
+    collateral_denom: &str,
+    close_time: Timestamp,
+) -> StdResult<u64> {
+    let legacy = LEGACY_MARKETS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (market_id, old) in &legacy {
+        let status = match (old.resolved, old.winning_outcome.as_deref()) {
+            (false, _) => MarketStatus::Open,
+            (true, Some("YES" | "NO")) => MarketStatus::Finalized,
+            (true, _) => MarketStatus::Voided,
+        };
+        let market = Market {
+            creator: old.creator.clone(),
+            description: old.description.clone(),
+            shares_yes: old.shares_yes,
+            shares_no: old.shares_no,
+            total_liquidity: old.total_liquidity,
+            total_liquidity_shares: old.total_liquidity_shares,
+            collateral_denom: collateral_denom.to_string(),
+            status,
+            // Unknown, the close time alone bounds their event time
+            created_at: Timestamp::default(),
+            close_time,
+            resolution_time: close_time,
+            winning_outcome: match status {
+                MarketStatus::Voided => Some("INVALID".to_string()),
+                _ => old.winning_outcome.clone(),
+            },
+            price_yes: old.price_yes,
+            price_no: old.price_no,
+            void_fallback: VoidFallback::default(),
+            condition: None,
+            group_id: None,
+            resolver: Resolver::Creator,
+            creator_bond: Uint128::zero(),
+            spam_deposit: Uint128::zero(),
+            volume: Uint128::zero(),
+            max_position: None,
+            max_open_interest: None,
+            open_interest: Uint128::zero(),
+            // Only the liquidity of a legacy market is known to back it
+            collateral: old.total_liquidity,
+            event_time: None,
+            paused: false,
+            shutdown: false,
+            visibility: Visibility::Public,
+        };
+        MARKETS.save(storage, *market_id, &market)?;
+        if status == MarketStatus::Open {
+            CLOSE_QUEUE.save(storage, (close_time.seconds(), *market_id), &Empty {})?;
+        }
+
+        let provided = LIQUIDITY_PROVIDERS
+            .prefix(*market_id)
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, provider)| provider.contributed_liquidity))
+            .sum::<StdResult<Uint128>>()?;
+        let initial = old.total_liquidity.saturating_sub(provided);
+        if !initial.is_zero() {
+            LIQUIDITY_PROVIDERS.update(
+                storage,
+                (*market_id, old.creator.clone()),
+                |record| -> StdResult<LiquidityProvider> {
+                    let mut provider = record.unwrap_or(LiquidityProvider {
+                        address: old.creator.clone(),
+                        contributed_liquidity: Uint128::zero(),
+                    });
+                    provider.contributed_liquidity += initial;
+                    Ok(provider)
+                },
+            )?;
+        }
+    }
+
+    Ok(legacy.len() as u64)
+}
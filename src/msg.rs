@@ -1,19 +1,74 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Addr, Coin, Decimal, Timestamp, Uint128};
+
+use crate::state::{
+    Comparator, Config, MarketCondition, MarketStatus, OracleReport, PositionLimit, ResolutionStep,
+    Resolver, Role, Visibility, VoidFallback,
+};
 
 #[cw_serde]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    /// Settles disputed proposals, defaults to the instantiator
+    pub arbiter: Option<String>,
+    /// Bond required to propose or dispute an outcome
+    pub proposal_bond: Uint128,
+    /// Seconds a proposal stays open to disputes
+    pub liveness: u64,
+    /// Price-feed contracts price markets resolve from, through their median
+    pub price_feeds: Vec<String>,
+    /// Seconds after the observation time within which a price must be published
+    pub max_price_age: u64,
+    /// Largest confidence interval accepted, in basis points of the price
+    pub max_confidence_bps: u64,
+    /// Disputes a proposal can go through before the arbiter decides
+    pub max_dispute_rounds: u32,
+    /// Bond creators post with CreateMarket to resolve their markets
+    pub creator_bond: Uint128,
+    /// Receives part of slashed creator bonds, defaults to the instantiator
+    pub treasury: Option<String>,
+    /// Share of a slashed creator bond going to the treasury, in basis points
+    pub slash_treasury_bps: u64,
+    /// Reward paid from the keeper pool per market or claim a keeper processes
+    pub keeper_reward: Uint128,
+    /// Collateral denom clients default to for new markets
+    pub default_collateral: String,
+    /// Fee on share purchases going to the treasury, in basis points
+    pub trading_fee_bps: u64,
+    /// Smallest initial liquidity a market can be created with
+    pub min_liquidity: Uint128,
+    /// Largest initial liquidity a market can be created with, unbounded if unset
+    pub max_liquidity: Option<Uint128>,
+    /// Smallest initial liquidity for specific collateral denoms
+    pub collateral_min_liquidity: Vec<Coin>,
+    /// Fee paid to the treasury to create a market, per collateral denom
+    pub creation_fee: Vec<Coin>,
+    /// Deposit held with every market, per collateral denom, returned once it
+    /// finalizes with `min_activity` traded
+    pub spam_deposit: Vec<Coin>,
+    /// Collateral a market must trade for the spam deposit to be returned
+    pub min_activity: Uint128,
+    /// Lets anyone create markets, otherwise only holders of the MarketCreator role
+    pub open_market_creation: bool,
+    /// Contract asked whether an address may trade, provide liquidity or
+    /// transfer shares
+    pub compliance: Option<String>,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    /// Owner of deployments from before the config existed, which kept none
+    pub owner: Option<String>,
+    /// Settings for deployments from before the config existed
+    pub config: Option<InstantiateMsg>,
+    /// Close and resolution time of markets migrated without a schedule,
+    /// defaults to the migration time
+    pub legacy_close_time: Option<Timestamp>,
+}
 
 #[cw_serde]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
-    // Increment {},
-    // Reset {
-    //     count: i32,
-    // },
-    CreateMarket {
-        description: String,
-        initial_liquidity: Uint128,
-    },
+    CreateMarket(CreateMarketMsg),
     AddLiquidity {
         market_id: u64,
         amount: Uint128,
@@ -32,22 +87,484 @@ pub enum ExecuteMsg {
         outcome: String,
         amount: Uint128,
     },
+    /// Reports the outcome: "YES", "NO" or "INVALID" to void the market
     ResolveMarket {
         market_id: u64,
         winning_outcome: String,
+        /// URIs or hashes backing the outcome, kept in the resolution history
+        #[serde(default)]
+        evidence: Vec<String>,
+        /// When the event happened, trades from then on are reverted
+        event_time: Option<Timestamp>,
+    },
+    /// Makes the outcome of a resolved market final so payouts can be made
+    FinalizeMarket {
+        market_id: u64,
+    },
+    /// Proposes the outcome of a closed market, bonded with `proposal_bond`.
+    /// Only holders of the Resolver role may report an `event_time`.
+    ProposeOutcome {
+        market_id: u64,
+        outcome: String,
+        #[serde(default)]
+        evidence: Vec<String>,
+        event_time: Option<Timestamp>,
+    },
+    /// Flips the tentative outcome of a proposal by staking twice the stake of
+    /// the previous round
+    DisputeOutcome {
+        market_id: u64,
+        outcome: String,
+        #[serde(default)]
+        evidence: Vec<String>,
+    },
+    /// Arbiter decision once a dispute has used up its rounds
+    ArbitrateDispute {
+        market_id: u64,
+        outcome: String,
+        #[serde(default)]
+        evidence: Vec<String>,
+        /// Replaces the event time reported with the proposal, None clearing it
+        event_time: Option<Timestamp>,
+    },
+    /// Committee member vote on the outcome, the market resolves once an outcome
+    /// reaches the committee threshold
+    VoteOutcome {
+        market_id: u64,
+        outcome: String,
+        #[serde(default)]
+        evidence: Vec<String>,
+    },
+    /// Records the feed prices of a price market inside its observation window,
+    /// which closes `max_price_age` seconds after the observation time
+    ObservePrice {
+        market_id: u64,
+    },
+    /// Resolves an oracle or price market from its oracle contract or the
+    /// configured price feed. A price market nobody observed in its window
+    /// resolves INVALID.
+    ResolveFromOracle {
+        market_id: u64,
+    },
+    /// Deletes an untouched market and refunds its creator. Once the market has
+    /// trades or outside liquidity only the owner can cancel it, which voids it.
+    CancelMarket {
+        market_id: u64,
+    },
+    /// Burns `amount` YES and NO shares of a conditional market and credits the
+    /// same amount of the parent outcome shares the market is conditional on
+    MergePositions {
+        market_id: u64,
+        amount: Uint128,
+    },
+    /// Groups open markets of the sender into mutually exclusive outcomes of one event
+    CreateEventGroup {
+        description: String,
+        market_ids: Vec<u64>,
+    },
+    /// Burns `amount` NO shares in each of `market_ids` and returns `amount` collateral
+    /// for every market beyond the first, plus `amount` YES shares in every other
+    /// market of the group
+    ConvertNoPositions {
+        group_id: u64,
+        market_ids: Vec<u64>,
+        amount: Uint128,
+    },
+    /// Pays out the sender's shares and pool share of a finalized or voided market
+    Claim {
+        market_id: u64,
+    },
+    /// Adds the attached funds to the pool keeper rewards are paid from
+    FundKeeperPool {},
+    /// Owner-only change of the contract settings, unset fields are left as they are
+    UpdateConfig(UpdateConfigMsg),
+    /// Offers ownership to `owner`, who has to accept it before `expiry`
+    ProposeNewOwner {
+        owner: String,
+        expiry: Option<Timestamp>,
+    },
+    /// Takes over ownership offered to the sender
+    AcceptOwnership {},
+    /// Leaves the contract without an owner for good
+    RenounceOwnership {},
+    /// Halts buys, sells and liquidity adds in one market, or in all of them
+    /// without `market_id`. Claims and withdrawals keep working.
+    Pause {
+        market_id: Option<u64>,
+    },
+    Unpause {
+        market_id: Option<u64>,
+    },
+    /// Moves `amount` shares of `outcome` to `recipient`
+    TransferShares {
+        market_id: u64,
+        recipient: String,
+        outcome: String,
+        amount: Uint128,
+    },
+    /// Freezes one market, or a page of the unsettled ones without
+    /// `market_id`, at its last prices, owner only. Each market pays out of
+    /// its own collateral; holders and LPs exit through Claim.
+    EmergencyShutdown {
+        market_id: Option<u64>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Replaces the position and open interest caps of a market, owner only.
+    /// Unset limits are lifted.
+    SetMarketLimits {
+        market_id: u64,
+        max_position: Option<PositionLimit>,
+        max_open_interest: Option<Uint128>,
+    },
+    /// Lets `addresses` into a private market, creator only
+    AddParticipants {
+        market_id: u64,
+        addresses: Vec<String>,
+    },
+    /// Takes `addresses` off the participants of a private market, creator only.
+    /// Shares they already hold can still be sold and claimed.
+    RemoveParticipants {
+        market_id: u64,
+        addresses: Vec<String>,
+    },
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    /// Keeper action closing up to `limit` markets past their close time
+    CloseExpired {
+        limit: Option<u32>,
+    },
+    /// Keeper action finalizing up to `limit` markets whose outcome is ready
+    FinalizeResolved {
+        limit: Option<u32>,
+    },
+    /// Keeper action paying out up to `limit` holders of a settled market
+    ProcessClaims {
+        market_id: u64,
+        limit: Option<u32>,
+    },
+}
+
+/// Keeper actions run by the chain itself, e.g. from a clock module. They do
+/// the same work as their execute counterparts but pay no reward.
+#[cw_serde]
+pub enum SudoMsg {
+    CloseExpired { limit: Option<u32> },
+    FinalizeResolved { limit: Option<u32> },
+    ProcessClaims { market_id: u64, limit: Option<u32> },
+}
+
+#[cw_serde]
+pub struct CreateMarketMsg {
+    pub description: String,
+    pub initial_liquidity: Uint128,
+    /// Trading stops at this time
+    pub close_time: Timestamp,
+    /// The outcome can be reported from this time on
+    pub resolution_time: Timestamp,
+    /// Payout rule if the market resolves as invalid, defaults to an equal split
+    pub void_fallback: Option<VoidFallback>,
+    /// Makes this market conditional on the outcome of another one
+    pub condition: Option<MarketCondition>,
+    /// Who reports the outcome, defaults to the creator
+    pub resolver: Option<ResolverMsg>,
+    /// Caps the position of each address
+    pub max_position: Option<PositionLimit>,
+    /// Caps the collateral traders can put into the market
+    pub max_open_interest: Option<Uint128>,
+    /// Defaults to public
+    pub visibility: Option<Visibility>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct UpdateConfigMsg {
+    pub arbiter: Option<String>,
+    pub proposal_bond: Option<Uint128>,
+    pub liveness: Option<u64>,
+    pub price_feeds: Option<Vec<String>>,
+    pub max_price_age: Option<u64>,
+    pub max_confidence_bps: Option<u64>,
+    pub max_dispute_rounds: Option<u32>,
+    pub creator_bond: Option<Uint128>,
+    pub treasury: Option<String>,
+    pub slash_treasury_bps: Option<u64>,
+    pub keeper_reward: Option<Uint128>,
+    pub default_collateral: Option<String>,
+    pub trading_fee_bps: Option<u64>,
+    pub min_liquidity: Option<Uint128>,
+    pub max_liquidity: Option<Uint128>,
+    pub collateral_min_liquidity: Option<Vec<Coin>>,
+    pub creation_fee: Option<Vec<Coin>>,
+    pub spam_deposit: Option<Vec<Coin>>,
+    pub min_activity: Option<Uint128>,
+    pub open_market_creation: Option<bool>,
+    /// An empty address removes the compliance contract
+    pub compliance: Option<String>,
+}
+
+#[cw_serde]
+pub enum ResolverMsg {
+    Creator {},
+    Committee {
+        members: Vec<String>,
+        threshold: u32,
+        /// Seconds after the resolution time before a stalled vote voids the market
+        timeout: u64,
+    },
+    Oracle {
+        sources: Vec<OracleSourceMsg>,
+        /// Share of dissenting sources tolerated, in basis points
+        tolerance_bps: u64,
+    },
+    /// Resolved from the price feeds set in the contract config
+    PriceFeed {
+        asset: String,
+        comparator: Comparator,
+        threshold: Decimal,
+        /// Between the close and resolution times of the market
+        observation_time: Timestamp,
+        /// Spread between feeds tolerated, in basis points of the median
+        tolerance_bps: u64,
     },
 }
 
+#[cw_serde]
+pub struct OracleSourceMsg {
+    pub contract: String,
+    pub query_key: String,
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
-    // GetCount returns the current count as a json-encoded number
-    #[returns(GetCountResponse)]
-    GetCount {},
+    #[returns(Config)]
+    Config {},
+    #[returns(PendingOwnerResponse)]
+    PendingOwner {},
+    #[returns(HasRoleResponse)]
+    HasRole { role: Role, address: String },
+    #[returns(RoleMembersResponse)]
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(MarketResponse)]
+    GetMarket { market_id: u64 },
+    #[returns(MarketsResponse)]
+    ListMarkets {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        /// Also list private markets
+        include_private: Option<bool>,
+    },
+    #[returns(ParticipantsResponse)]
+    Participants {
+        market_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(EventGroupResponse)]
+    GetEventGroup { group_id: u64 },
+    #[returns(ProposalResponse)]
+    GetProposal { market_id: u64 },
+    #[returns(DisputeRoundsResponse)]
+    GetDisputeRounds { market_id: u64 },
+    #[returns(OracleReportsResponse)]
+    GetOracleReports { market_id: u64 },
+    #[returns(CreatorReputationResponse)]
+    CreatorReputation { creator: String },
+    #[returns(VotesResponse)]
+    GetVotes { market_id: u64 },
+    #[returns(KeeperPoolResponse)]
+    KeeperPool { denom: String },
+    /// Every step of the resolution of a market, oldest first
+    #[returns(ResolutionHistoryResponse)]
+    ResolutionHistory { market_id: u64 },
+    /// How much more `address` can buy before hitting the limits of a market
+    #[returns(HeadroomResponse)]
+    Headroom { market_id: u64, address: String },
 }
 
 // We define a custom struct for each query response
 #[cw_serde]
-pub struct GetCountResponse {
-    pub count: i32,
+pub struct MarketResponse {
+    pub market_id: u64,
+    pub creator: Addr,
+    pub description: String,
+    pub collateral_denom: String,
+    pub status: MarketStatus,
+    pub created_at: Timestamp,
+    pub close_time: Timestamp,
+    pub resolution_time: Timestamp,
+    /// Seconds left until trading closes, zero once closed
+    pub seconds_to_close: u64,
+    /// Seconds left until the market can be resolved, zero once reached
+    pub seconds_to_resolution: u64,
+    pub shares_yes: Uint128,
+    pub shares_no: Uint128,
+    pub total_liquidity: Uint128,
+    pub price_yes: Uint128,
+    pub price_no: Uint128,
+    pub winning_outcome: Option<String>,
+    pub void_fallback: VoidFallback,
+    pub condition: Option<MarketCondition>,
+    pub group_id: Option<u64>,
+    pub resolver: Resolver,
+    pub creator_bond: Uint128,
+    pub spam_deposit: Uint128,
+    pub volume: Uint128,
+    pub max_position: Option<PositionLimit>,
+    pub max_open_interest: Option<Uint128>,
+    pub open_interest: Uint128,
+    pub collateral: Uint128,
+    pub event_time: Option<Timestamp>,
+    /// Whether trading is halted, by this market's switch or the global one
+    pub paused: bool,
+    pub shutdown: bool,
+    pub visibility: Visibility,
+}
+
+#[cw_serde]
+pub struct MarketsResponse {
+    pub markets: Vec<MarketResponse>,
+}
+
+#[cw_serde]
+pub struct EventGroupResponse {
+    pub group_id: u64,
+    pub creator: Addr,
+    pub description: String,
+    pub markets: Vec<GroupMarketPrice>,
+    /// Sum of the YES prices, scaled like market prices
+    pub price_sum: Uint128,
+    /// Whether the YES prices sum to one within tolerance
+    pub sums_to_one: bool,
+}
+
+#[cw_serde]
+pub struct GroupMarketPrice {
+    pub market_id: u64,
+    pub status: MarketStatus,
+    pub price_yes: Uint128,
+}
+
+#[cw_serde]
+pub struct ProposalResponse {
+    pub proposer: Addr,
+    pub outcome: String,
+    pub bond: Uint128,
+    pub expires_at: Timestamp,
+    pub round: u32,
+}
+
+#[cw_serde]
+pub struct CreatorReputationResponse {
+    pub creator: Addr,
+    pub markets_created: u64,
+    pub resolutions_upheld: u64,
+    pub resolutions_overturned: u64,
+    pub total_slashed: Uint128,
+}
+
+#[cw_serde]
+pub struct OracleReportsResponse {
+    pub reports: Vec<OracleReport>,
+}
+
+#[cw_serde]
+pub struct HasRoleResponse {
+    pub has_role: bool,
+}
+
+#[cw_serde]
+pub struct ParticipantsResponse {
+    pub market_id: u64,
+    pub participants: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct RoleMembersResponse {
+    pub role: Role,
+    pub members: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct PendingOwnerResponse {
+    pub pending_owner: Option<Addr>,
+    pub expiry: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct KeeperPoolResponse {
+    pub denom: String,
+    pub balance: Uint128,
+    /// Paid per processed market or claim while the balance lasts
+    pub reward: Uint128,
+}
+
+#[cw_serde]
+pub struct HeadroomResponse {
+    pub market_id: u64,
+    pub address: Addr,
+    pub max_position: Option<PositionLimit>,
+    /// Left under the position limit, in its unit. None without a limit.
+    pub position_headroom: Option<Uint128>,
+    pub open_interest: Uint128,
+    /// Collateral left under the open interest limit. None without a limit.
+    pub open_interest_headroom: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct ResolutionHistoryResponse {
+    pub market_id: u64,
+    pub steps: Vec<ResolutionStep>,
+}
+
+#[cw_serde]
+pub struct DisputeRoundsResponse {
+    /// Round 0 is the proposal itself
+    pub rounds: Vec<DisputeRoundInfo>,
+    pub tentative_outcome: String,
+    pub expires_at: Timestamp,
+    /// Stake needed to dispute, `None` once the arbiter has to decide
+    pub next_stake: Option<Uint128>,
+    pub max_rounds: u32,
+}
+
+#[cw_serde]
+pub struct DisputeRoundInfo {
+    pub round: u32,
+    pub staker: Addr,
+    pub outcome: String,
+    pub stake: Uint128,
+    pub staked_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct VotesResponse {
+    pub votes: Vec<Vote>,
+    pub tally: Vec<OutcomeTally>,
+    pub threshold: u32,
+    /// Votes are accepted until this time
+    pub deadline: Timestamp,
+}
+
+#[cw_serde]
+pub struct Vote {
+    pub voter: Addr,
+    pub outcome: String,
+}
+
+#[cw_serde]
+pub struct OutcomeTally {
+    pub outcome: String,
+    pub votes: u32,
 }
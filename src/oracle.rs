@@ -0,0 +1,17 @@
+//! Query interface of the oracle contracts markets can resolve from.
+
+use cosmwasm_schema::{cw_serde, QueryResponses};
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum OracleQueryMsg {
+    /// Reported answer for `key`
+    #[returns(AnswerResponse)]
+    Answer { key: String },
+}
+
+#[cw_serde]
+pub struct AnswerResponse {
+    /// "YES", "NO" or "INVALID" in any case, `None` until the oracle has answered
+    pub answer: Option<String>,
+}
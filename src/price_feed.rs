@@ -0,0 +1,32 @@
+//! Query interface of the Pyth-style price-feed contract price markets
+//! resolve from.
+
+use cosmwasm_schema::{cw_serde, QueryResponses};
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum PriceFeedQueryMsg {
+    /// Latest price published for the asset `id`
+    #[returns(PriceFeedResponse)]
+    PriceFeed { id: String },
+}
+
+#[cw_serde]
+pub struct PriceFeedResponse {
+    pub price_feed: PriceFeed,
+}
+
+#[cw_serde]
+pub struct PriceFeed {
+    pub id: String,
+    pub price: Price,
+}
+
+/// `price ± conf` scaled by `10^expo`, published at `publish_time` (unix seconds)
+#[cw_serde]
+pub struct Price {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
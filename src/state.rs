@@ -1,16 +1,114 @@
+use std::fmt;
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Coin, Decimal, Empty, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub struct State {
-    pub count: i32,
+pub struct Config {
+    /// Admin allowed to override market creators, none once renounced
+    pub owner: Option<Addr>,
+    /// Settles disputed outcome proposals
+    pub arbiter: Addr,
+    /// Bond, in the market collateral, posted to propose or dispute an outcome
+    pub proposal_bond: Uint128,
+    /// Seconds a proposal can be disputed before it becomes final
+    pub liveness: u64,
+    /// Price-feed contracts price markets resolve from, through their median
+    pub price_feeds: Vec<Addr>,
+    /// Seconds after the observation time within which a price must be published
+    pub max_price_age: u64,
+    /// Largest confidence interval accepted, in basis points of the price
+    pub max_confidence_bps: u64,
+    /// Disputes a proposal can go through before the arbiter decides
+    pub max_dispute_rounds: u32,
+    /// Bond, in the market collateral, creators post to resolve their markets
+    pub creator_bond: Uint128,
+    /// Receives its share of slashed creator bonds
+    pub treasury: Addr,
+    /// Share of a slashed creator bond going to the treasury, in basis points.
+    /// The rest goes to the stakers who overturned the resolution.
+    pub slash_treasury_bps: u64,
+    /// Paid from the keeper pool, in the market collateral, for each market or
+    /// claim a keeper processes
+    pub keeper_reward: Uint128,
+    /// Collateral denom clients default to for new markets
+    pub default_collateral: String,
+    /// Fee on share purchases going to the treasury, in basis points
+    pub trading_fee_bps: u64,
+    /// Bounds on the initial liquidity of a new market
+    pub min_liquidity: Uint128,
+    pub max_liquidity: Uint128,
+    /// Minimum initial liquidity for specific collateral denoms, overriding
+    /// `min_liquidity`
+    pub collateral_min_liquidity: Vec<Coin>,
+    /// Paid to the treasury to create a market, per collateral denom. Markets in
+    /// other denoms pay none.
+    pub creation_fee: Vec<Coin>,
+    /// Held, per collateral denom, with every new market and returned to the
+    /// creator once it finalizes with `min_activity` traded. Otherwise it goes
+    /// to the keeper pool.
+    pub spam_deposit: Vec<Coin>,
+    /// Collateral a market must trade for its creator to get the spam deposit back
+    pub min_activity: Uint128,
+    /// Lets anyone create markets, otherwise only holders of the MarketCreator role
+    pub open_market_creation: bool,
+    /// Halts trading and liquidity adds in every market
+    pub paused: bool,
+    /// Screens buyers, sellers, liquidity providers and share transfers
+    pub compliance: Option<Addr>,
+}
+
+impl Config {
+    pub fn is_owner(&self, address: &Addr) -> bool {
+        self.owner.as_ref() == Some(address)
+    }
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Ownership transfer waiting for the new owner to accept it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingOwner {
     pub owner: Addr,
+    /// The proposal can no longer be accepted from this time on
+    pub expiry: Option<Timestamp>,
 }
 
-pub const STATE: Item<State> = Item::new("state");
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("pending_owner");
+
+/// Permissions the owner hands out on top of its own
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Creates markets while creation is not open to everyone
+    MarketCreator,
+    /// Reports the outcome of creator-resolved markets
+    Resolver,
+    /// Pauses and unpauses trading
+    Pauser,
+    /// Changes the trading fee and keeper reward
+    FeeManager,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::MarketCreator => "market_creator",
+            Role::Resolver => "resolver",
+            Role::Pauser => "pauser",
+            Role::FeeManager => "fee_manager",
+        }
+    }
+}
+
+/// Role holders: (role, address)
+pub const ROLES: Map<(&str, &Addr), Empty> = Map::new("roles");
+
+/// Addresses allowed into private markets: (market_id, address)
+pub const PARTICIPANTS: Map<(u64, &Addr), Empty> = Map::new("participants");
 
 //storages
 pub const MARKETS: Map<u64, Market> = Map::new("markets");
@@ -18,6 +116,29 @@ pub const MARKET_COUNT: Item<u64> = Item::new("market_count");
 pub const BUYERS: Map<(u64, Addr), Buyer> = Map::new("buyers");
 pub const LIQUIDITY_PROVIDERS: Map<(u64, Addr), LiquidityProvider> =
     Map::new("liquidity_providers");
+/// Conditional markets by parent: (parent_id, child_id)
+pub const CHILD_MARKETS: Map<(u64, u64), Empty> = Map::new("child_markets");
+pub const EVENT_GROUPS: Map<u64, EventGroup> = Map::new("event_groups");
+pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
+/// Stakes behind a proposal: (market_id, round), round 0 being the proposal
+pub const DISPUTE_ROUNDS: Map<(u64, u32), DisputeRound> = Map::new("dispute_rounds");
+/// Source reports: (market_id, source index)
+pub const ORACLE_REPORTS: Map<(u64, u32), OracleReport> = Map::new("oracle_reports");
+pub const CREATOR_STATS: Map<&Addr, CreatorStats> = Map::new("creator_stats");
+/// Fills of each market in execution order: (market_id, trade index)
+pub const TRADES: Map<(u64, u64), Trade> = Map::new("trades");
+/// Funds keeper rewards are paid from, by denom
+pub const KEEPER_POOL: Map<&str, Uint128> = Map::new("keeper_pool");
+/// Markets waiting to be closed: (close time in seconds, market_id)
+pub const CLOSE_QUEUE: Map<(u64, u64), Empty> = Map::new("close_queue");
+/// Markets that may be finalizable from a time on: (seconds, market_id).
+/// Entries are only hints, markets are checked again when processed.
+pub const FINALIZE_QUEUE: Map<(u64, u64), Empty> = Map::new("finalize_queue");
+/// Audit trail of the resolution steps of a market: (market_id, step index)
+pub const RESOLUTION_LOG: Map<(u64, u32), ResolutionStep> = Map::new("resolution_log");
+/// Committee votes: (market_id, member) -> outcome
+pub const VOTES: Map<(u64, &Addr), String> = Map::new("votes");
+pub const EVENT_GROUP_COUNT: Item<u64> = Item::new("event_group_count");
 
 // State Structures
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -28,10 +149,289 @@ pub struct Market {
     pub shares_no: Uint128,
     pub total_liquidity: Uint128,
     pub total_liquidity_shares: Uint128,
-    pub resolved: bool,
+    /// Native denom every trade, deposit and payout of this market is made in
+    pub collateral_denom: String,
+    pub status: MarketStatus,
+    pub created_at: Timestamp,
+    pub close_time: Timestamp,
+    pub resolution_time: Timestamp,
     pub winning_outcome: Option<String>,
     pub price_yes: Uint128,
     pub price_no: Uint128,
+    pub void_fallback: VoidFallback,
+    /// Parent outcome this market is conditional on
+    pub condition: Option<MarketCondition>,
+    /// Event group of mutually exclusive markets this market belongs to
+    pub group_id: Option<u64>,
+    pub resolver: Resolver,
+    /// Creator bond not yet put behind a resolution or returned
+    pub creator_bond: Uint128,
+    /// Spam deposit not yet returned or forfeited
+    pub spam_deposit: Uint128,
+    /// Collateral traded through buys and sells
+    pub volume: Uint128,
+    /// Cap on the position of each address, checked on buys, transfers and merges
+    pub max_position: Option<PositionLimit>,
+    /// Cap on the open interest, checked on buys, conversions and merges
+    pub max_open_interest: Option<Uint128>,
+    /// Collateral traders have put into the market, net of sells
+    pub open_interest: Uint128,
+    /// Collateral backing the shares and the pool: liquidity and buys, less
+    /// what sells, withdrawals and claims paid out
+    pub collateral: Uint128,
+    /// When the real-world event happened, reported at resolution. Trades
+    /// from then on are reverted when the market settles.
+    pub event_time: Option<Timestamp>,
+    /// Halts trading and liquidity adds in this market
+    pub paused: bool,
+    /// Voided by an emergency shutdown, paying out at its last prices
+    pub shutdown: bool,
+    pub visibility: Visibility,
+}
+
+impl Market {
+    /// Status of the market at `now`. An open market whose close time has passed is
+    /// reported as closed even if no transaction has persisted the transition yet.
+    pub fn status_at(&self, now: Timestamp) -> MarketStatus {
+        if self.status == MarketStatus::Open && now >= self.close_time {
+            MarketStatus::Closed
+        } else {
+            self.status
+        }
+    }
+}
+
+/// Lifecycle of a market:
+/// Open -> Closed -> (Proposed -> Disputed ->) Resolved -> Finalized,
+/// with Voided as the terminal state for markets that cannot be answered.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketStatus {
+    /// Trading and liquidity changes are allowed
+    Open,
+    /// Trading has stopped, waiting for the outcome
+    Closed,
+    /// An outcome has been proposed and can still be challenged
+    Proposed,
+    /// The proposed outcome is being disputed
+    Disputed,
+    /// The outcome is known but not final yet
+    Resolved,
+    /// The market was cancelled or could not be answered
+    Voided,
+    /// The outcome is final and payouts can be claimed
+    Finalized,
+}
+
+impl fmt::Display for MarketStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            MarketStatus::Open => "open",
+            MarketStatus::Closed => "closed",
+            MarketStatus::Proposed => "proposed",
+            MarketStatus::Disputed => "disputed",
+            MarketStatus::Resolved => "resolved",
+            MarketStatus::Voided => "voided",
+            MarketStatus::Finalized => "finalized",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/// The market only settles normally if `market_id` resolves to `outcome`,
+/// otherwise it is voided
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MarketCondition {
+    pub market_id: u64,
+    pub outcome: String,
+}
+
+/// Who reports the outcome of a market
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolver {
+    /// A holder of the Resolver role resolves, or anyone through a bonded proposal
+    #[default]
+    Creator,
+    /// `threshold` of `members` must vote for the same outcome within `timeout`
+    /// seconds of the resolution time, otherwise the market can be voided
+    Committee {
+        members: Vec<Addr>,
+        threshold: u32,
+        timeout: u64,
+    },
+    /// Anyone can resolve from the majority answer of the sources. When more
+    /// than `tolerance_bps` of them dissent the answer goes to dispute instead.
+    Oracle {
+        sources: Vec<OracleSource>,
+        tolerance_bps: u64,
+    },
+    /// Anyone can resolve from the configured price feeds: YES when the median
+    /// price of `asset` at `observation_time` compares to `threshold` as
+    /// `comparator` says. The prices are read, or recorded by ObservePrice,
+    /// within `max_price_age` of the observation time, and resolve INVALID when
+    /// nobody did. Feeds spread by more than `tolerance_bps` of the median
+    /// send the outcome to dispute.
+    PriceFeed {
+        asset: String,
+        comparator: Comparator,
+        threshold: Decimal,
+        observation_time: Timestamp,
+        tolerance_bps: u64,
+    },
+}
+
+/// Oracle contract answering the query `query_key`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct OracleSource {
+    pub contract: Addr,
+    pub query_key: String,
+}
+
+/// What a source reported when the market was resolved from it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct OracleReport {
+    pub source: Addr,
+    pub query_key: String,
+    /// Outcome for oracle sources, price for price feeds
+    pub answer: String,
+    pub reported_at: Timestamp,
+}
+
+/// How the observed price is compared to the threshold of a price market
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparator {
+    Above,
+    AtOrAbove,
+    Below,
+    AtOrBelow,
+}
+
+impl Comparator {
+    pub fn holds(self, price: Decimal, threshold: Decimal) -> bool {
+        match self {
+            Comparator::Above => price > threshold,
+            Comparator::AtOrAbove => price >= threshold,
+            Comparator::Below => price < threshold,
+            Comparator::AtOrBelow => price <= threshold,
+        }
+    }
+}
+
+/// Outcome proposed through the optimistic resolution flow. Each dispute
+/// round flips the tentative `outcome` and reopens the dispute window.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Proposal {
+    pub proposer: Addr,
+    pub outcome: String,
+    pub bond: Uint128,
+    /// End of the window in which the tentative outcome can be disputed
+    pub expires_at: Timestamp,
+    /// Disputes so far
+    pub round: u32,
+}
+
+/// Track record of a market creator's resolutions
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct CreatorStats {
+    pub markets_created: u64,
+    pub resolutions_upheld: u64,
+    pub resolutions_overturned: u64,
+    /// Bonds lost to overturned resolutions, summed across collaterals
+    pub total_slashed: Uint128,
+}
+
+/// One step towards the outcome of a market, kept as a permanent record
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ResolutionStep {
+    pub action: ResolutionAction,
+    pub actor: Addr,
+    /// Outcome put forward by this step, if any
+    pub outcome: Option<String>,
+    /// URIs or hashes of the evidence the actor supplied
+    pub evidence: Vec<String>,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolutionAction {
+    Proposed,
+    Disputed,
+    Voted,
+    OracleReported,
+    Arbitrated,
+    Finalized,
+    Cancelled,
+    Shutdown,
+}
+
+/// Stake posted on an outcome in one round of a dispute
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DisputeRound {
+    pub staker: Addr,
+    pub outcome: String,
+    pub stake: Uint128,
+    pub staked_at: Timestamp,
+}
+
+/// Mutually exclusive binary markets of which at most one resolves YES
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct EventGroup {
+    pub creator: Addr,
+    pub description: String,
+    pub market_ids: Vec<u64>,
+    pub collateral_denom: String,
+}
+
+/// Who can trade and provide liquidity in a market
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    /// Open to anyone and listed
+    #[default]
+    Public,
+    /// Open to the creator and the participants it adds, unlisted by default
+    Private,
+}
+
+/// How outcome shares are valued when a market resolves as invalid
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VoidFallback {
+    /// Every YES and NO share pays half a unit of collateral
+    #[default]
+    EqualSplit,
+    /// Shares pay out at the prices the market closed at
+    LastTradedPrices,
+}
+
+/// Cap on what a single address can hold in a market
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionLimit {
+    /// Collateral spent on buys, net of sells
+    Collateral { amount: Uint128 },
+    /// YES and NO shares held
+    Shares { amount: Uint128 },
+}
+
+impl PositionLimit {
+    pub fn amount(&self) -> Uint128 {
+        match self {
+            PositionLimit::Collateral { amount } | PositionLimit::Shares { amount } => *amount,
+        }
+    }
+
+    /// Size of `buyer`'s position in the unit of the limit
+    pub fn position(&self, buyer: &Buyer) -> Uint128 {
+        match self {
+            PositionLimit::Collateral { .. } => buyer.spent,
+            PositionLimit::Shares { .. } => buyer.shares_yes + buyer.shares_no,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -39,6 +439,38 @@ pub struct Buyer {
     pub address: Addr,
     pub shares_yes: Uint128,
     pub shares_no: Uint128,
+    /// Paid for buys reverted after the event time, refunded with the claim
+    #[serde(default)]
+    pub reverted_cost: Uint128,
+    /// Received for sells reverted after the event time, held back from the claim
+    #[serde(default)]
+    pub reverted_proceeds: Uint128,
+    /// Collateral spent on buys, net of sells
+    #[serde(default)]
+    pub spent: Uint128,
+}
+
+/// Fill against the pool, kept so it can be unwound if it came after the event
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Trade {
+    pub trader: Addr,
+    pub side: TradeSide,
+    pub outcome: String,
+    /// Collateral paid for a buy or received for a sell
+    pub collateral: Uint128,
+    pub shares: Uint128,
+    /// YES and NO pool shares before and after the fill
+    pub pool_before: (Uint128, Uint128),
+    pub pool_after: (Uint128, Uint128),
+    pub executed_at: Timestamp,
+    pub reverted: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeSide {
+    Buy,
+    Sell,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]